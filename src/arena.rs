@@ -0,0 +1,250 @@
+//contains matches between two engines, to find out whether a change makes the engine stronger.
+//the engines play games in pairs from the same opening, each engine playing both sides once
+
+use crate::Board;
+use crate::enums::{ EngineKind, SprtSettings, LoadSaveError, LoadSaveError::* };
+use crate::engine::{ new_engine, SearchLimits };
+use crate::eval::Weights;
+use crate::tt::TranspositionTable;
+
+use std::fs;
+use std::time::Instant;
+
+//a move may take this much longer than the time limit before it loses the game on time, in milliseconds
+const TIME_MARGIN: u128 = 200;
+
+//an engine with its evaluation weights
+pub struct Contestant {
+	pub kind: EngineKind,
+	pub weights: Weights,
+	pub label: String,             //printed in the results, the engine name and the weights file if one was given
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+	Win,
+	Draw,
+	Loss,
+}
+
+//wins, draws and losses of the first contestant
+#[derive(Clone, Copy, Default)]
+pub struct Score {
+	pub wins: usize,
+	pub draws: usize,
+	pub losses: usize,
+}
+
+impl Score {
+	pub fn add(&mut self, result: GameResult) {
+		match result {
+			GameResult::Win => self.wins += 1,
+			GameResult::Draw => self.draws += 1,
+			GameResult::Loss => self.losses += 1,
+		}
+	}
+
+	pub fn games(&self) -> usize {
+		self.wins + self.draws + self.losses
+	}
+
+	pub fn ratio(&self) -> f64 {
+		//the points per game, a draw is half a point
+		(self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+	}
+
+	pub fn variance(&self) -> f64 {
+		//of the points per game, 0 if every game ended the same
+		let s = self.ratio();
+		(self.wins as f64 * (1.0 - s).powi(2) + self.draws as f64 * (0.5 - s).powi(2) + self.losses as f64 * s.powi(2)) / self.games() as f64
+	}
+
+	pub fn elo(&self) -> (f64, f64) {
+		//the elo difference and the margin of its 95% confidence interval. The difference is infinite if a side scored every point,
+		//the margin is infinite if every game ended the same, as the spread of the results is unknown then
+		let n = self.games() as f64;
+		let s = self.ratio();
+		let variance = self.variance();
+		if variance == 0.0 { return (elo_from_ratio(s), f64::INFINITY) };
+		let margin = 1.96 * (variance / n).sqrt();
+		(elo_from_ratio(s), (elo_from_ratio((s + margin).min(1.0)) - elo_from_ratio((s - margin).max(0.0))) / 2.0)
+	}
+}
+
+pub fn ratio_from_elo(elo: f64) -> f64 {
+	1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn elo_from_ratio(s: f64) -> f64 {
+	//the elo difference at which the stronger player is expected to score s points per game
+	400.0 * (s / (1.0 - s)).log10()
+}
+
+impl SprtSettings {
+	pub fn bounds(&self) -> (f64, f64) {
+		//the log likelihood ratios at which elo0 and elo1 are accepted
+		((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+	}
+
+	pub fn llr(&self, score: &Score) -> f64 {
+		//the log likelihood ratio of elo1 against elo0 of the generalized sprt. Wins, draws and losses are three outcomes
+		//with the frequencies of the match, so the points per game are about normally distributed with their variance.
+		//the frequencies count half a game of every outcome more, so a match where every game ended the same has a spread
+		//and no single game decides it
+		let n = score.games() as f64;
+		let [w, d, l] = [score.wins, score.draws, score.losses].map(|c| (c as f64 + 0.5) / (n + 1.5));
+		let s = w + 0.5 * d;
+		let variance = w * (1.0 - s).powi(2) + d * (0.5 - s).powi(2) + l * s.powi(2);
+		let (s0, s1) = (ratio_from_elo(self.elo0), ratio_from_elo(self.elo1));
+		n * (s1 - s0) * (2.0 * s - s0 - s1) / (2.0 * variance)
+	}
+}
+
+pub fn load_openings(path: &str) -> Result<Vec<Board>, LoadSaveError> {
+	//one position per line, like 'position' prints them. Empty lines and lines starting with '#' are skipped
+	let content = fs::read_to_string(path).map_err(|_| FileNotFound)?;
+	let mut openings = vec![];
+	for line in content.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+		let board = Board::from_position_string(line).map_err(|_| CorruptedOpenings)?;
+		if board.num_players != 2 || board.is_over() { return Err(CorruptedOpenings) };
+		openings.push(board);
+	}
+	if openings.is_empty() { return Err(CorruptedOpenings) };
+	Ok(openings)
+}
+
+pub fn play_game(contestants: [&Contestant; 2], opening: &Board, limits: SearchLimits, max_moves: usize) -> (Option<usize>, String) {
+	//plays one game, contestants[0] moves first from the opening. Returns the index of the winning contestant,
+	//None for a draw, and how the game ended. A game still running after max_moves moves is a draw
+	let mut board = opening.clone();
+	board.move_sequence.clear();
+	let tts = [TranspositionTable::new(18), TranspositionTable::new(18)];
+	let mut engines = [0, 1].map(|i| new_engine(contestants[i].kind, &tts[i], &contestants[i].weights));
+	//the opening may have the second player to move
+	let first = board.to_move_indices().0;
+
+	while !board.is_over() && board.move_sequence.len() < max_moves {
+		let mover = (board.to_move_indices().0 + 2 - first) % 2;
+		let begin_time = Instant::now();
+		let res = engines[mover].best_move(&board, limits);
+		if limits.time.is_some_and(|t| begin_time.elapsed().as_millis() > t.as_millis() + TIME_MARGIN) {
+			return (Some(1 - mover), format!("{} lost on time", contestants[mover].label));
+		}
+		match res.best {
+			Some(m) if board.check_move(&m).is_ok() => board.mv(&m),
+			Some(_) => return (Some(1 - mover), format!("{} played an illegal move", contestants[mover].label)),
+			None => return (Some(1 - mover), format!("{} found no move", contestants[mover].label)),
+		}
+	}
+	//the last move allowed may still reach the goal
+	match board.goal_reached() {
+		Some(winner) => (Some((winner + 2 - first) % 2), format!("after {} moves", board.move_sequence.len())),
+		None => (None, format!("move limit of {} moves reached", max_moves)),
+	}
+}
+
+pub fn run_match(contestants: [&Contestant; 2], games: usize, openings: &[Board], limits: SearchLimits, max_moves: usize,
+sprt: Option<&SprtSettings>) -> Score {
+	//plays the games one after another, printing every result, and returns the score of the first contestant.
+	//with an sprt the log likelihood ratio is printed after every game and the match stops once it leaves the bounds
+	let mut score = Score::default();
+	for game in 0..games {
+		//the same opening twice in a row, with the colours swapped
+		let opening = &openings[game / 2 % openings.len()];
+		let swapped = game % 2 == 1;
+		let order = if swapped { [contestants[1], contestants[0]] } else { contestants };
+		let (winner, how) = play_game(order, opening, limits, max_moves);
+		let result = match winner {
+			None => GameResult::Draw,
+			Some(w) if (w == 1) == swapped => GameResult::Win,
+			Some(_) => GameResult::Loss,
+		};
+		score.add(result);
+		let text = match winner {
+			Some(w) => format!("{} wins", order[w].label),
+			None => String::from("draw"),
+		};
+		println!("    Game {:>4}:    {} - {}    {}, {}    (+{} ={} -{})",
+			game+1, order[0].label, order[1].label, text, how, score.wins, score.draws, score.losses);
+
+		let Some(sprt) = sprt else { continue };
+		let (lower, upper) = sprt.bounds();
+		let llr = sprt.llr(&score);
+		println!("                  LLR {:>6.2}    ({:.2}, {:.2})", llr, lower, upper);
+		if llr >= upper {
+			println!("    SPRT accepted elo1 after {} games, {} is more likely {} than {} elo stronger", game+1, contestants[0].label, sprt.elo1, sprt.elo0);
+			return score;
+		}
+		if llr <= lower {
+			println!("    SPRT accepted elo0 after {} games, {} is more likely {} than {} elo stronger", game+1, contestants[0].label, sprt.elo0, sprt.elo1);
+			return score;
+		}
+	}
+	if sprt.is_some() { println!("    SPRT undecided after {} games", games) };
+	score
+}
+
+pub fn print_score(contestants: [&Contestant; 2], score: &Score) {
+	if score.games() == 0 { return };
+	println!("    {} vs {}:    {} wins, {} draws, {} losses    ({:.1}%)",
+		contestants[0].label, contestants[1].label, score.wins, score.draws, score.losses, 100.0 * score.ratio());
+	let (elo, margin) = score.elo();
+	if !elo.is_finite() {
+		println!("    Elo difference: unknown, {} won every game", contestants[if elo > 0.0 { 0 } else { 1 }].label);
+	} else if score.draws == score.games() {
+		println!("    Elo difference: {:.1}, every game was drawn so there is no error margin", elo);
+	} else if !margin.is_finite() {
+		println!("    Elo difference: {:.1}, too few games to give an error margin", elo);
+	} else {
+		println!("    Elo difference: {:.1} +/- {:.1}", elo, margin);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SPRT: SprtSettings = SprtSettings { elo0: 0.0, elo1: 10.0, alpha: 0.05, beta: 0.05 };
+
+	fn _score(wins: usize, draws: usize, losses: usize) -> Score {
+		Score { wins, draws, losses }
+	}
+
+	#[test]
+	fn sprt_bounds() {
+		//ln(beta / (1 - alpha)) and ln((1 - beta) / alpha)
+		let (lower, upper) = SPRT.bounds();
+		assert!((lower + 2.944).abs() < 0.001);
+		assert!((upper - 2.944).abs() < 0.001);
+	}
+
+	#[test]
+	fn sprt_llr() {
+		//about 150 elo stronger, clearly above elo1, and as much weaker, clearly below elo0.
+		//the same score over more games is more certain
+		assert!(SPRT.llr(&_score(60, 20, 20)) > 0.0);
+		assert!(SPRT.llr(&_score(20, 20, 60)) < 0.0);
+		assert!(SPRT.llr(&_score(240, 80, 80)) > SPRT.bounds().1);
+		assert!(SPRT.llr(&_score(80, 80, 240)) < SPRT.bounds().0);
+		//an even score is elo0 itself
+		assert!(SPRT.llr(&_score(30, 40, 30)) < 0.0);
+		//a single game decides nothing, even with every game won
+		assert!(SPRT.llr(&_score(1, 0, 0)) > 0.0);
+		assert!(SPRT.llr(&_score(1, 0, 0)) < SPRT.bounds().1);
+	}
+
+	#[test]
+	fn elo() {
+		//variance 0.15 per game, so the margin is 1.96 * sqrt(0.15 / 100) points per game around 0.5, 53.16 elo
+		let (elo, margin) = _score(30, 40, 30).elo();
+		assert!(elo.abs() < 1e-9);
+		assert!((margin - 53.16).abs() < 0.01);
+		assert!((elo_from_ratio(ratio_from_elo(100.0)) - 100.0).abs() < 1e-9);
+		//every game drawn leaves the margin unknown, every game won the difference
+		let (elo, margin) = _score(0, 10, 0).elo();
+		assert!(elo.abs() < 1e-9);
+		assert!(margin.is_infinite());
+		assert!(_score(10, 0, 0).elo().0 == f64::INFINITY);
+		assert!(_score(0, 0, 10).elo().0 == f64::NEG_INFINITY);
+	}
+}
@@ -1,118 +1,337 @@
-use std::collections::{ HashMap, HashSet };
+use std::collections::HashMap;
 
 use crate::enums::{ Move, Move::*,
+    Direction, Direction::*,
+    Turn::*,
+    Orientation,
     MoveError, MoveError::*,
     ParseError, ParseError::*,
-    Notation,
+    Notation, Notation::*,
     Player, Player::* };
 
 use crate::move_from_str;
+use crate::helpers::{ string_from_move, string_from_square, square_from_str };
+use crate::zobrist::{ WALL_KEYS, SQUARE_KEYS, WALLS_LEFT_KEYS, TO_MOVE_KEYS, SIZE_KEYS };
 //use crate::graph::Graph;
 
+pub const MIN_SIZE: usize = 3;
+pub const MAX_SIZE: usize = 11;
+
 #[derive(Clone)]
 pub struct Board {
-    pub walls: u128,               //bitmap. First 64 horizontal, other 64 vertival, 64*i + 8*y + x is bit for wall [i, x, y]
-    pub players: [[usize; 2]; 2],
+    pub size: usize,               //number of squares per side, odd and between MIN_SIZE and MAX_SIZE
+    pub walls: [u128; 2],          //bitmaps of horizontal and vertical walls, (size-1)*y + x is bit for wall [i, x, y]
+    open: [u128; 4],               //bitmaps of squares a step north, east, south or west is possible from, size*y + x is bit for [x, y].
+                                   //derived from walls and kept up to date by place_wall and remove_wall
+    goals: [u128; 4],              //bitmaps of the goal squares of every player
+    paths: [Option<[u128; 4]>; 4], //a path to the goal of every player, as bitmaps of the squares it steps north, east, south or west from.
+                                   //forgotten when the pawn moves or a wall cuts the path, see has_path
+    pub num_players: usize,        //2 or 4. Only the first num_players entries of players and walls_left are used
+    pub players: [[usize; 2]; 4],
     pub to_move: Player,
-    pub walls_left: [usize; 2],
+    pub walls_left: [usize; 4],
     pub move_sequence: Vec<Move>,
+    pub hash: u64,                 //zobrist hash of size, walls, players, walls_left and to_move. Kept up to date by mv
 }
 
 impl Default for Board {
     fn default() -> Board {
-        Board {
-            walls: 0, 
-            players: [[4,0],[4,8]],
-            to_move: Player1,
-            walls_left: [10, 10],
-            move_sequence: vec![],
-        }
+        Board::with_size(9)
     }
 }
 
 impl Board {
-    fn is_wall(&self, i: usize, x: usize, y: usize) -> bool {
-        self.walls & (1 << (64*i + 8*y + x)) != 0
+    fn wall_bit(&self, x: usize, y: usize) -> usize {
+        (self.size-1)*y + x
+    }
+
+    pub fn is_wall(&self, i: usize, x: usize, y: usize) -> bool {
+        self.walls[i] & (1 << self.wall_bit(x, y)) != 0
     }
 
-    fn place_wall(&mut self, i: usize, x: usize, y: usize) {
-        self.walls = self.walls | (1 << (64*i + 8*y + x));
+    pub fn place_wall(&mut self, i: usize, x: usize, y: usize) {
+        if !self.is_wall(i, x, y) { self.hash ^= WALL_KEYS[i][self.wall_bit(x, y)] };
+        self.walls[i] |= 1 << self.wall_bit(x, y);
+        self.refresh_open(i, x, y);
     }
 
     fn remove_wall(&mut self, i: usize, x: usize, y: usize) {
-        self.walls = self.walls & !(1 << (64*i + 8*y + x));
+        if self.is_wall(i, x, y) { self.hash ^= WALL_KEYS[i][self.wall_bit(x, y)] };
+        self.walls[i] &= !(1 << self.wall_bit(x, y));
+        self.refresh_open(i, x, y);
     }
 
-    pub fn to_move_indices(&self) -> (usize, usize) {
-        match self.to_move {
-            Player1 => (0, 1),
-            Player2 => (1, 0)
+    fn square_bit(&self, [x, y]: [usize; 2]) -> u128 {
+        1 << (self.size*y + x)
+    }
+
+    fn wall_steps(i: usize, x: usize, y: usize) -> [([usize; 2], Direction); 4] {
+        //the four steps crossing wall [i, x, y], as square and direction
+        match i {
+            0 => [([x, y], North), ([x+1, y], North), ([x, y+1], South), ([x+1, y+1], South)],
+            _ => [([x, y], East), ([x, y+1], East), ([x+1, y], West), ([x+1, y+1], West)],
+        }
+    }
+
+    fn refresh_open(&mut self, i: usize, x: usize, y: usize) {
+        //recomputes the four steps crossing wall [i, x, y] from the walls. Overlapping walls, like the ones of fill, may block the same step
+        for (square, dir) in Board::wall_steps(i, x, y) {
+            let bit = self.square_bit(square);
+            if self.blocked_by_wall(square, dir) { self.open[dir as usize] &= !bit } else { self.open[dir as usize] |= bit };
+        }
+        //a path stays a path unless one of its steps was closed
+        for path in self.paths.iter_mut() {
+            if path.is_some_and(|path| (0..4).any(|d| path[d] & !self.open[d] != 0)) { *path = None };
+        }
+    }
+
+    fn blocked_by_wall(&self, [x, y]: [usize; 2], dir: Direction) -> bool {
+        //the step in dir from [x, y] crosses a wall. The step must not leave the board
+        let n = self.size-1;
+        match dir {
+            North => (x != n && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)),
+            East => (y != n && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)),
+            South => (x != n && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)),
+            West => (y != n && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)),
+        }
+    }
+
+    fn square_key(&self, p: usize) -> u64 {
+        let [x, y] = self.players[p];
+        SQUARE_KEYS[p][self.size*y + x]
+    }
+
+    pub fn compute_hash(&self) -> u64 {
+        //hashes the board from scratch. mv updates the hash incrementally instead
+        let mut hash = SIZE_KEYS[self.size];
+        for (i, keys) in WALL_KEYS.iter().enumerate() {
+            for (bit, key) in keys.iter().enumerate() {
+                if self.walls[i] & (1 << bit) != 0 { hash ^= key };
+            }
+        }
+        for p in 0..self.num_players {
+            hash ^= self.square_key(p);
+            hash ^= WALLS_LEFT_KEYS[p][self.walls_left[p]];
         }
+        hash ^= TO_MOVE_KEYS[self.to_move.index()];
+        hash
+    }
+
+    pub fn to_move_indices(&self) -> (usize, usize) {
+        //the player to move and the player moving after them
+        let tm = self.to_move.index();
+        (tm, (tm+1) % self.num_players)
+    }
+
+    pub fn pawns(&self) -> &[[usize; 2]] {
+        &self.players[..self.num_players]
     }
 
     pub fn new() -> Board {
         Board {..Default::default()}
     }
 
-    pub fn new5x5() -> Board {
-        Board {
-            walls: 80770148214459885546621745580772163713, 
-            players: [[4,2],[4,6]],
+    pub fn with_size(size: usize) -> Board {
+        Board::with_players(size, 2)
+    }
+
+    pub fn with_players(size: usize, num_players: usize) -> Board {
+        //pawns start in the middle of their side of the board.
+        //the walls are shared between the players and grow with the area of the board, 20 on the usual 9x9 board
+        let walls = ((size-1)*(size-1)*20).div_ceil(64*num_players);
+        let mut b = Board {
+            size,
+            walls: [0, 0],
+            open: [0; 4],
+            goals: [0; 4],
+            paths: [None; 4],
+            num_players,
+            players: [[0, 0]; 4],
             to_move: Player1,
-            walls_left: [3, 3],
+            walls_left: [0; 4],
             move_sequence: vec![],
+            hash: 0,
+        };
+        let (m, n) = (size/2, size-1);
+        for p in 0..num_players {
+            b.players[p] = [[m, 0], [0, m], [m, n], [n, m]][b.side(p)];
+            b.walls_left[p] = walls;
+        }
+        //without walls every step is open, except the ones leaving the board
+        let all: u128 = (1 << (size*size)) - 1;
+        let column: u128 = (0..size).map(|y| 1 << (size*y)).sum();
+        let row: u128 = (1 << size) - 1;
+        b.open = [all >> size, all & !(column << n), all & !row, all & !column];
+        for p in 0..num_players {
+            b.goals[p] = [row << (size*n), column << n, row, column][b.side(p)];
+        }
+        b.hash = b.compute_hash();
+        b
+    }
+
+    pub fn is_valid_size(size: usize) -> bool {
+        size % 2 == 1 && (MIN_SIZE..=MAX_SIZE).contains(&size)
+    }
+
+    fn side(&self, p: usize) -> usize {
+        //the side player p starts on, 0 south, 1 west, 2 north, 3 east. Players follow each other clockwise
+        match self.num_players {
+            2 => 2*p,
+            _ => p,
         }
     }
 
+    pub fn is_goal(&self, p: usize, [x, y]: [usize; 2]) -> bool {
+        //every player has to reach the side opposite to where they started
+        self.goals[p] & self.square_bit([x, y]) != 0
+    }
+
     pub fn mv(&mut self, m: &Move) {
         //actually applies a move
         //detect illegal moves before calling! Might panic if illegal move is passed
         let tm = self.to_move_indices().0;
         match m {
-            Step(mut d) => {
-                let mut code: Vec<usize> = vec![];
-                while {
-                    code.push(d % 10);
-                    d /= 10;
-                    d != 0
-                } {};
-                for i in code.iter().rev() {
-                    match i {
-                    1 => self.players[tm][1] += 1, //N
-                    2 => self.players[tm][0] += 1, //E
-                    3 => self.players[tm][1] -= 1, //S
-                    4 => self.players[tm][0] -= 1, //W
-                    _ => (),
-                    }
+            Wall { orientation, x, y } => { 
+                self.place_wall(*orientation as usize, *x, *y);
+                self.hash ^= WALLS_LEFT_KEYS[tm][self.walls_left[tm]];
+                self.walls_left[tm] -= 1;
+                self.hash ^= WALLS_LEFT_KEYS[tm][self.walls_left[tm]];
+            },
+            _ => {
+                self.hash ^= self.square_key(tm);
+                self.paths[tm] = None;
+                self.players[tm] = Board::landing(self.players[tm], m);
+                self.hash ^= self.square_key(tm);
+            },
+        }
+        self.hash ^= TO_MOVE_KEYS[tm];
+        self.to_move = Player::from_index((tm+1) % self.num_players);
+        self.hash ^= TO_MOVE_KEYS[self.to_move.index()];
+        self.move_sequence.push(*m);
+    }
 
-                }
+    pub fn unmake(&mut self, m: &Move) {
+        //takes back m, which has to be the last move played. Restores the board exactly, hash and move_sequence included
+        self.hash ^= TO_MOVE_KEYS[self.to_move.index()];
+        self.to_move = Player::from_index((self.to_move.index() + self.num_players-1) % self.num_players);
+        let tm = self.to_move.index();
+        self.hash ^= TO_MOVE_KEYS[tm];
+        match m {
+            Wall { orientation, x, y } => {
+                self.remove_wall(*orientation as usize, *x, *y);
+                self.hash ^= WALLS_LEFT_KEYS[tm][self.walls_left[tm]];
+                self.walls_left[tm] += 1;
+                self.hash ^= WALLS_LEFT_KEYS[tm][self.walls_left[tm]];
             },
-            Wall([i, x, y]) => { 
-                self.place_wall(*i,*x,*y);
-                self.walls_left[tm] -= 1;
+            _ => {
+                self.hash ^= self.square_key(tm);
+                self.paths[tm] = None;
+                self.players[tm] = Board::origin(self.players[tm], m);
+                self.hash ^= self.square_key(tm);
             },
         }
-        match self.to_move {
-            Player1 => self.to_move = Player2,
-            Player2 => self.to_move = Player1
+        self.move_sequence.pop();
+    }
+
+    pub fn landing(square: [usize; 2], m: &Move) -> [usize; 2] {
+        //the square a pawn on square ends up on after the pawn move m. The move has to be possible
+        match *m {
+            Step(d) => Board::neighbour(square, d),
+            Jump(d) => Board::neighbour(Board::neighbour(square, d), d),
+            DiagonalJump(d, t) => Board::neighbour(Board::neighbour(square, d), d.turn(t)),
+            Wall { .. } => square,
         }
-        self.move_sequence.push(m.clone());
     }
 
-    pub fn mv_new_no_memory(&self, m: &Move) -> Board {
-        //makes the move but doesnt save it to memory
-        let mut b = self.mv_new(m);
-        b.move_sequence.clear();
-        b
+    fn origin(square: [usize; 2], m: &Move) -> [usize; 2] {
+        //the inverse of landing
+        match *m {
+            Step(d) => Board::neighbour(square, d.opposite()),
+            Jump(d) => Board::neighbour(Board::neighbour(square, d.opposite()), d.opposite()),
+            DiagonalJump(d, t) => Board::neighbour(Board::neighbour(square, d.turn(t).opposite()), d.opposite()),
+            Wall { .. } => square,
+        }
     }
 
-    pub fn mv_new(&self, m: &Move) -> Board {
-        //returns a new board with the given move applies. Check if move is legal before calling!
+    pub fn start_position(&self) -> Board {
+        //undoes every move of move_sequence, returning the position the game started from
         let mut b = self.clone();
-        b.mv(m);
+        for m in self.move_sequence.iter().rev() {
+            b.unmake(m);
+        }
         b
     }
 
+    pub fn to_position_string(&self) -> String {
+        //encodes the position as 'size / pawns / walls left / player to move / walls', like '9 / e1 e9 / 10 10 / 1 / d3h e6v'.
+        //a four player position lists four pawns and four numbers of walls left
+        let mut walls = vec![];
+        for i in 0..=1 {
+            for y in 0..self.size-1 {
+                for x in 0..self.size-1 {
+                    if self.is_wall(i, x, y) {
+                        walls.push(string_from_move(&Board::wall(i, x, y), self, Absolute));
+                    }
+                }
+            }
+        }
+        let pawns: Vec<String> = self.pawns().iter().map(|p| string_from_square(*p)).collect();
+        let walls_left: Vec<String> = self.walls_left[..self.num_players].iter().map(|w| w.to_string()).collect();
+        format!("{} / {} / {} / {} / {}",
+            self.size,
+            pawns.join(" "),
+            walls_left.join(" "),
+            self.to_move_indices().0+1,
+            walls.join(" ")).trim_end().to_string()
+    }
+
+    pub fn from_position_string(s: &str) -> Result<Board, ParseError> {
+        //the inverse of to_position_string. The board has no move history
+        let sections: Vec<Vec<&str>> = s.split('/').map(|sec| sec.split_whitespace().collect()).collect();
+        let [size, pawns, walls_left, to_move, walls] = &sections[..] else { return Err(PositionIncorrect) };
+
+        //the number of pawns decides the number of players
+        if ![2, 4].contains(&pawns.len()) || walls_left.len() != pawns.len() { return Err(PositionIncorrect) };
+        let mut b = match size[..] {
+            [size] => match size.parse() {
+                Ok(size) if Board::is_valid_size(size) => Board::with_players(size, pawns.len()),
+                _ => return Err(PositionIncorrect),
+            },
+            _ => return Err(PositionIncorrect),
+        };
+        for (p, pawn) in pawns.iter().enumerate() {
+            match square_from_str(pawn) {
+                Some([x, y]) if x < b.size && y < b.size && !b.players[..p].contains(&[x, y]) => b.players[p] = [x, y],
+                _ => return Err(PositionIncorrect),
+            }
+        }
+        //nobody can have more walls left than at the start
+        for (p, w) in walls_left.iter().enumerate() {
+            match w.parse() {
+                Ok(w) if w <= b.walls_left[p] => b.walls_left[p] = w,
+                _ => return Err(PositionIncorrect),
+            }
+        }
+        b.to_move = match to_move[..] {
+            [tm] => match tm.parse::<usize>() {
+                Ok(tm) if (1..=b.num_players).contains(&tm) => Player::from_index(tm-1),
+                _ => return Err(PositionIncorrect),
+            },
+            _ => return Err(PositionIncorrect),
+        };
+        for w in walls {
+            match move_from_str(w, &b, Absolute) {
+                Ok(Wall { orientation, x, y }) if x < b.size-1 && y < b.size-1 && b.wall_fits(orientation as usize, x, y) => {
+                    b.place_wall(orientation as usize, x, y)
+                },
+                _ => return Err(PositionIncorrect),
+            }
+        }
+        if (0..b.num_players).any(|p| b.dist_to_goal(p).is_none()) { return Err(PositionIncorrect) };
+        b.hash = b.compute_hash();
+        Ok(b)
+    }
+
     pub fn from(s: &str, move_errors: &HashMap<MoveError, String>, notation: Notation) -> Result<Board, String> {
         let mut b = Board::new();
         match b.extend(s, move_errors, notation) {
@@ -121,181 +340,111 @@ impl Board {
         }
     }
 
-    fn check_move(&mut self, mv: &Move) -> Result<(), MoveError> {
+    pub fn check_move(&mut self, mv: &Move) -> Result<(), MoveError> {
         //checks if a move is illegal. Horrible case distinction
         //to_move is true if its player 0's turn, else its false. This is to check a move sequence ocrrectly
 
-        let (tm, ntm) = self.to_move_indices();
-        let [mut x, mut y] = self.players[tm];
-        let [a,b] = self.players[ntm];
+        if self.is_over() { return Err(GameIsOver) };
+
+        let n = self.size-1;
+        let tm = self.to_move.index();
         match mv {
-            //first checking single step
-            Step(1) => {
-                if y == 8 { return Err(EdgeOfBoard) };
-                if (x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)) { return Err(BlockedByWall) };
-                if [a, b] == [x, y+1] {return Err(BlockedByOpponent) };
-            },
-            Step(2) => {
-                if x == 8 { return Err(EdgeOfBoard) };
-                if (y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)) { return Err(BlockedByWall) };
-                if [a, b] == [x+1, y] {return Err(BlockedByOpponent) };
-            },
-            Step(3) => {
-                if y == 0 { return Err(EdgeOfBoard) };
-                if (x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)) { return Err(BlockedByWall) };
-                if [a, b] == [x, y-1] {return Err(BlockedByOpponent) };
-            },
-            Step(4) => {
-                if x == 0 { return Err(EdgeOfBoard) };
-                if (y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)) { return Err(BlockedByWall) };
-                if [a, b] == [x-1, y] {return Err(BlockedByOpponent) };
-            },
-            //checking jumps
-            Step(mut d) => {
-                let mut code: Vec<usize> = vec![];
-                while {
-                    code.push(d % 10);
-                    d /= 10;
-                    d != 0
-                } {};
-                
-                //check if the jump is blocked by a wall
-                match code[1] {
-                    1 => {
-                        if y == 8 { return Err(EdgeOfBoard) };
-                        if (x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)) { return Err(BlockedByWall) };
-                    },
-                    2 => {
-                        if x == 8 { return Err(EdgeOfBoard) };
-                        if (y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)) { return Err(BlockedByWall) };
-                    },
-                    3 => {
-                        if y == 0 { return Err(EdgeOfBoard) };
-                        if (x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)) { return Err(BlockedByWall) };
-                    },
-                    4 => {
-                        if x == 0 { return Err(EdgeOfBoard) };
-                        if (y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)) { return Err(BlockedByWall) };
-                    },
-                    _ => () //cannot happen
-                };
-
-                //opponent needs to be in that direction, if yes, move to tile of opponent
-                match code[1] {
-                    1 => if b == 0 || [x,y] != [a,b-1] { return Err(OpponentNotThere); } else { y += 1 },
-                    2 => if a == 0 || [x,y] != [a-1,b] { return Err(OpponentNotThere); } else { x += 1 },
-                    3 => if [x,y] != [a,b+1] { return Err(OpponentNotThere); } else { y -= 1 },
-                    4 => if [x,y] != [a+1,b] { return Err(OpponentNotThere); } else { x -= 1 },
-                    _ => (), // cannot happen
-                };
-                
-                //jump in a straight line, need no wall or edge of board behind opponent
-                if code[0] == code[1] {
-                    match code[0] {
-                        1 => {
-                            if y == 8 { return Err(EdgeOfBoard) };
-                            if (x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)) { return Err(BlockedByWall) };
-                        },
-                        2 => {
-                            if x == 8 { return Err(EdgeOfBoard) };
-                            if (y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)) { return Err(BlockedByWall) };
-                        },
-                        3 => {
-                            if y == 0 { return Err(EdgeOfBoard) };
-                            if (x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)) { return Err(BlockedByWall) };
-                        },
-                        4 => {
-                            if x == 0 { return Err(EdgeOfBoard) };
-                            if (y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)) { return Err(BlockedByWall) };
-                        },
-                        _ => (), //cannot happen
-                    }
-                } else {
-                    //jump diagonal, need wall or edge of board behind
-                    match code[1] {
-                        1 => if !((y == 8 || x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y))) { return Err(SpaceBehindFree) },
-                        2 => if !((x == 8 || y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1))) { return Err(SpaceBehindFree) },
-                        3 => if !((y == 0 || x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1))) { return Err(SpaceBehindFree) },
-                        4 => if !((x == 0 || y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1))) { return Err(SpaceBehindFree) },
-                        _ => (), //cannot happen
-                    };
-                    //check if the next step is valid
-                    match code[0] {
-                        1 => {
-                            if y == 8 { return Err(EdgeOfBoard) };
-                            if (x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)) { return Err(BlockedByWall) };
-                        },
-                        2 => {
-                            if x == 8 { return Err(EdgeOfBoard) };
-                            if (y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)) { return Err(BlockedByWall) };
-                        },
-                        3 => {
-                            if y == 0 { return Err(EdgeOfBoard) };
-                            if (x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)) { return Err(BlockedByWall) };
-                        },
-                        4 => {
-                            if x == 0 { return Err(EdgeOfBoard) };
-                            if (y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)) { return Err(BlockedByWall) };
-                        },
-                        _ => (), //cannot happen
-                    }
-                }
-            }
-            Wall([i, x, y]) => { 
+            Wall { orientation, x, y } => { 
+                let i = *orientation as usize;
+                if *x >= n || *y >= n { return Err(WallOutsideBoard) };
                 if self.walls_left[tm] == 0 { return Err(NoWallsLeft) };
-                if self.is_wall(0, *x, *y) || self.is_wall(1, *x, *y) { return Err(SpaceOccupied) };
-                match i {
-                    0 => if (*x != 0 && self.is_wall(0, *x-1, *y)) || (*x != 7 && self.is_wall(0, *x+1, *y)) 
-                    { return Err(SpaceOccupied) },
-                    1 => if (*y != 0 && self.is_wall(1, *x, *y-1)) || (*y != 7 && self.is_wall(1, *x, *y+1)) 
-                    { return Err(SpaceOccupied) },
-                    _ => () //cannot happen
-                };
-                self.place_wall(*i, *x, *y);
-                if self.dist_to_goal(0).is_none() {
-                    self.remove_wall(*i, *x, *y);
-                    return Err(P1NoPath);
+                if !self.wall_fits(i, *x, *y) { return Err(SpaceOccupied) };
+                //only walls cutting a remembered path need to be placed and searched around
+                let steps = Board::wall_steps(i, *x, *y);
+                let cuts = |path: &[u128; 4]| steps.iter().any(|(square, dir)| path[*dir as usize] & self.square_bit(*square) != 0);
+                if self.paths[..self.num_players].iter().all(|path| path.is_some_and(|path| !cuts(&path))) { return Ok(()) };
+                self.place_wall(i, *x, *y);
+                let blocked = (0..self.num_players).find(|p| !self.has_path(*p));
+                self.remove_wall(i, *x, *y);
+                match blocked {
+                    Some(0) => return Err(P1NoPath),
+                    Some(1) => return Err(P2NoPath),
+                    Some(2) => return Err(P3NoPath),
+                    Some(_) => return Err(P4NoPath),
+                    None => (),
                 }
-                if self.dist_to_goal(1).is_none() {
-                    self.remove_wall(*i, *x, *y);
-                    return Err(P2NoPath);
+            }
+            _ => self.check_pawn_move(mv)?,
+        }
+        Ok(())
+    }
+
+    fn wall_fits(&self, i: usize, x: usize, y: usize) -> bool {
+        //a wall must not cross a wall at the same centre or overlap half of a wall of the same orientation
+        let n = self.size-1;
+        if self.is_wall(0, x, y) || self.is_wall(1, x, y) { return false };
+        match i {
+            0 => !((x != 0 && self.is_wall(0, x-1, y)) || (x+1 != n && self.is_wall(0, x+1, y))),
+            _ => !((y != 0 && self.is_wall(1, x, y-1)) || (y+1 != n && self.is_wall(1, x, y+1))),
+        }
+    }
+
+    pub fn check_pawn_move(&self, mv: &Move) -> Result<(), MoveError> {
+        //checks a step or jump of the player to move, whether or not the game is over.
+        //needs no mutable board, so parsers can use it to tell moves apart
+        let pos = self.players[self.to_move.index()];
+        match mv {
+            //first checking single step
+            Step(d) => {
+                if let Some(err) = self.step_error(pos, *d) { return Err(err) };
+                if self.occupied(Board::neighbour(pos, *d)) { return Err(BlockedByOpponent) };
+            },
+            //checking jumps. The direction leads to the pawn jumped over, the turn sends diagonal jumps sideways
+            Jump(d) | DiagonalJump(d, _) => {
+                let second = match mv { DiagonalJump(_, t) => d.turn(*t), _ => *d };
+
+                //a pawn needs to be in the direction, if yes, move to its tile
+                if let Some(err) = self.step_error(pos, *d) { return Err(err) };
+                let over = Board::neighbour(pos, *d);
+                if !self.occupied(over) { return Err(OpponentNotThere) };
+
+                //a diagonal jump needs a wall, the edge of the board or another pawn behind the pawn jumped over
+                if second != *d && self.step_possible(over, *d) && !self.occupied(Board::neighbour(over, *d)) {
+                    return Err(SpaceBehindFree);
                 }
-                self.remove_wall(*i, *x, *y);
+                //check if the next step is valid. Pawns are never jumped twice in one move
+                if let Some(err) = self.step_error(over, second) { return Err(err) };
+                if self.occupied(Board::neighbour(over, second)) { return Err(BlockedByOpponent) };
             }
+            Wall { .. } => (),
         }
         Ok(())
     }
 
     pub fn extend(&mut self, s: &str, move_errors: &HashMap<MoveError, String>, notation: Notation) -> Result<(), String> {
         //extends the board by the move sequence, returns an error if a move is illegal
+        let moves: Vec<&str> = s.split_whitespace().collect();
+        match self.try_extend(&moves, notation) {
+            Ok(()) => Ok(()),
+            Err((i, Some(err))) => Err(format!("Move {} ( {} ) is illegal, because: {}.\n    The board was not changed.",
+                i+1, moves[i], move_errors.get(&err).unwrap())),
+            Err((i, None)) => Err(format!("Input {} ( {} ) is invalid encoding.\n    The board was not changed.", i+1, moves[i])),
+        }
+    }
+
+    pub fn try_extend(&mut self, moves: &[&str], notation: Notation) -> Result<(), (usize, Option<MoveError>)> {
+        //plays the moves one after another. On the first move that is invalid encoding (None) or illegal,
+        //the board is left unchanged and the index of the move is returned
         let old_board = self.clone();
-        let (tm, ntm) = self.to_move_indices();
-        
-        for (i,m) in s.split_whitespace().enumerate() {
-            match move_from_str(m, self.players[tm], self.players[ntm], notation) {
-                Ok(mv) => {
-                    match self.check_move(&mv) {
-                    Ok(()) => (),
-                    Err(err) => { 
-                        *self = old_board;
-                        return Err(format!("Move {} ( {} ) is illegal, because: {}.\n    The board was not changed.",
-                            i+1, m, move_errors.get(&err).unwrap())) },
-                    };
-                    self.mv(&mv);
-                }
-                Err(_) => { *self = old_board; return Err(format!("Input {} ( {} ) is invalid encoding.\n    The board was not changed.",
-                    i+1, m)) },
-            };
+        for (i, m) in moves.iter().enumerate() {
+            let checked = move_from_str(m, self, notation).map_err(|_| None).and_then(|mv| self.check_move(&mv).map(|()| mv).map_err(Some));
+            match checked {
+                Ok(mv) => self.mv(&mv),
+                Err(err) => { *self = old_board; return Err((i, err)) },
+            }
         }
         Ok(())
     }
 
     pub fn extend_no_check(&mut self, s: &str, notation: Notation) {
         //extends the board by the move sequences, might panic if a move is illegal
-        let (tm, ntm) = self.to_move_indices();
-
         for m in s.split_whitespace() {
-            match move_from_str(m, self.players[tm], self.players[ntm], notation) {
+            match move_from_str(m, self, notation) {
                 Ok(mv) => self.mv(&mv),
                 _ => panic!("Not a move"),
             }
@@ -307,28 +456,32 @@ impl Board {
 
         let mut moves = Vec::new();
         //orders the legal move such that in the brute force dfs we walk towards the goal greedely. Dirty implementation, fix later
-        match self.to_move {
-            Player1 => {
-                for dir in [1,11,12,14,21,2,4,22,23,41,44,43,3,32,33,34] {
-                    if self.check_move(&Step(dir)).is_ok() { moves.push(Step(dir)) };
-                }
-            },
-            Player2 => {
-                for dir in [3,33,32,34,2,4,23,22,21,44,43,41,1,14,12,11] {
-                    if self.check_move(&Step(dir)).is_ok() { moves.push(Step(dir)) };
-                }
-            }
+        //forward is towards the goal of the player to move, right and left as seen walking forward
+        let f = Direction::ALL[self.side(self.to_move.index())];
+        let (r, l, b) = (f.turn(Right), f.turn(Left), f.opposite());
+        for m in [Step(f), Jump(f), DiagonalJump(f, Right), DiagonalJump(f, Left),
+                DiagonalJump(r, Left), Step(r), Step(l), Jump(r), DiagonalJump(r, Right),
+                DiagonalJump(l, Right), Jump(l), DiagonalJump(l, Left),
+                Step(b), DiagonalJump(b, Left), Jump(b), DiagonalJump(b, Right)] {
+            if self.check_move(&m).is_ok() { moves.push(m) };
         }
         for i in 0..=1 {
-            for x in 0..=7 {
-                for y in 0..=7 {
-                    if self.check_move(&Wall([i, x, y])).is_ok() { moves.push(Wall([i, x, y])) };
+            for x in 0..self.size-1 {
+                for y in 0..self.size-1 {
+                    let m = Board::wall(i, x, y);
+                    if self.check_move(&m).is_ok() { moves.push(m) };
                 }
             }
         }
         moves
     }
 
+    pub fn wall(i: usize, x: usize, y: usize) -> Move {
+        //the wall move for the wall bitmaps index i, 0 for horizontal and 1 for vertical walls
+        let orientation = if i == 0 { Orientation::Horizontal } else { Orientation::Vertical };
+        Wall { orientation, x, y }
+    }
+
     pub fn fill(&mut self, [x_0,y_0]: [usize; 2], [x_1,y_1]: [usize; 2]) {
         for i in 0..=1 {
             for x in x_0+1..x_1 {
@@ -350,7 +503,9 @@ impl Board {
     }
 
     pub fn try_fill_from_str(&mut self, input: String) -> Result<(), ParseError> {
-        if let [x_0,y_0,x_1,y_1] = input.split_whitespace().map(|x| x.parse::<usize>().unwrap()).collect::<Vec<_>>()[..] {
+        let chords: Vec<usize> = input.split_whitespace().map(|x| x.parse::<usize>()).collect::<Result<_, _>>().map_err(|_| FillChordsIncorrect)?;
+        if let [x_0,y_0,x_1,y_1] = chords[..] {
+            if x_0 >= x_1 || y_0 >= y_1 || x_1 > self.size-2 || y_1 > self.size-2 { return Err(FillChordsIncorrect) };
             self.fill([x_0,y_0], [x_1,y_1]);
         } else {
             return Err(FillChordsIncorrect)
@@ -358,99 +513,224 @@ impl Board {
         Ok(())
     }
 
-    fn check_step_to_goal(&self) -> bool {
-        let tm;
-        match self.to_move {
-            Player1 => tm = 0,
-            Player2 => tm = 1,
-        }
-        let [x, y] = self.players[tm];
-        match self.to_move {
-            Player1 => !((x != 8 && y != 8 && self.is_wall(0, x, y)) || (x != 0 && y != 8 && self.is_wall(0, x-1, y))),
-            Player2 => !((x != 8 && y != 0 && self.is_wall(0, x, y-1)) || (x != 0 && y != 0 && self.is_wall(0, x-1, y-1)))
-        }
+    pub fn goal_reached(&self) -> Option<usize> {
+        //returns the index of the player standing on their goal row, if there is one
+        (0..self.num_players).find(|p| self.is_goal(*p, self.players[*p]))
+    }
+
+    pub fn is_over(&self) -> bool {
+        //the game ends as soon as a pawn reaches its goal row, no moves are legal afterwards
+        self.goal_reached().is_some()
     }
 
     pub fn current_player_wins(&self) -> bool {
-        match self.to_move {
-            Player1 => self.players[0][1] >= 7 && self.check_step_to_goal() && self.players[1][1] != 0,
-            Player2 => self.players[1][1] <= 1 && self.check_step_to_goal() && self.players[0][1] != 8,
-        }
+        //the player to move can step onto their goal
+        !self.is_over() && self.dist_to_goal(self.to_move.index()) == Some(1)
     }
 
-    pub fn current_player_wins_5x5(&self) -> bool {
-        match self.to_move {
-            Player1 => self.players[0][1] >= 5 && self.check_step_to_goal() && self.players[1][1] != 0,
-            Player2 => self.players[1][1] <= 3 && self.check_step_to_goal() && self.players[0][1] != 8,
-        }
+    fn step_error(&self, [x, y]: [usize; 2], dir: Direction) -> Option<MoveError> {
+        //returns why the step in dir from [x, y] is impossible, if it is. Ignores the players
+        let n = self.size-1;
+        let edge = match dir {
+            North => y == n,
+            East => x == n,
+            South => y == 0,
+            West => x == 0,
+        };
+        if edge { return Some(EdgeOfBoard) };
+        if !self.step_possible([x, y], dir) { return Some(BlockedByWall) };
+        None
+    }
+
+    fn step_possible(&self, square: [usize; 2], dir: Direction) -> bool {
+        self.open[dir as usize] & self.square_bit(square) != 0
     }
 
-    fn step_possible(&self, [x, y]: [usize; 2], dir: usize) -> bool {
-        //returns if the step in dir from player is possible. dir is 1,2,3,4 for NESW
-        //ignores the players
+    fn neighbour([x, y]: [usize; 2], dir: Direction) -> [usize; 2] {
+        //the square next to [x, y] in dir. The step has to be possible
         match dir {
-            1 => {
-                if y == 8 { return false };
-                if (x != 8 && self.is_wall(0, x, y)) || (x != 0 && self.is_wall(0, x-1, y)) { return false };
-            },
-            2 => {
-                if x == 8 { return false };
-                if (y != 8 && self.is_wall(1, x, y)) || (y != 0 && self.is_wall(1, x, y-1)) { return false };
-            },
-            3 => {
-                if y == 0 { return false };
-                if (x != 8 && self.is_wall(0, x, y-1)) || (x != 0 && self.is_wall(0, x-1, y-1)) { return false };
-            },
-            4 => {
-                if x == 0 { return false };
-                if (y != 8 && self.is_wall(1, x-1, y)) || (y != 0 && self.is_wall(1, x-1, y-1)) { return false };
-            },
-            _ => (), //cant happen
+            North => [x, y+1],
+            East => [x+1, y],
+            South => [x, y-1],
+            West => [x-1, y],
         }
-        true
+    }
+
+    fn occupied(&self, square: [usize; 2]) -> bool {
+        self.pawns().contains(&square)
     }
 
     pub fn dist_to_goal(&self, p: usize) -> Option<usize> {
-        //p is the player to be evaluated
-        //panics if p is not 0 or 1
-        let [x, y] = self.players[p];
-        let g;
-        match p {
-            0 => g = 8,
-            1 => g = 0,
-            _ => g = 0, //cant happen
-        }
-        if y == g { return Some(0) };
-        let mut front = vec![[x, y]];
-        let mut found = HashSet::from([[x, y]]);
+        //p is the player to be evaluated. Flood fill over the bitmap of squares, ignoring the pawns.
+        //every round all squares one step further away are reached at once
+        let s = self.size;
+        let [n, e, so, w] = self.open;
+        let mut reached = self.square_bit(self.players[p]);
+        let mut steps = 0;
+        while reached & self.goals[p] == 0 {
+            let next = reached | (reached & n) << s | (reached & e) << 1 | (reached & so) >> s | (reached & w) >> 1;
+            if next == reached { return None };
+            reached = next;
+            steps += 1;
+        }
+        Some(steps)
+    }
+
+    fn has_path(&mut self, p: usize) -> bool {
+        //whether player p can still reach their goal. Walls that do not cut the remembered path need no search at all,
+        //which makes checking all wall moves cheap. A path found with a wall placed stays a path once the wall is removed
+        if self.paths[p].is_none() { self.paths[p] = self.shortest_path(p) };
+        self.paths[p].is_some()
+    }
+
+    fn shortest_path(&self, p: usize) -> Option<[u128; 4]> {
+        //flood fill like dist_to_goal, remembering the squares first reached in every round.
+        //then walks back from the goal, each time to a square of the previous round a step leads from
+        let s = self.size;
+        let [n, e, so, w] = self.open;
+        let mut fronts = [0; MAX_SIZE*MAX_SIZE];
+        fronts[0] = self.square_bit(self.players[p]);
+        let mut reached = fronts[0];
         let mut steps = 0;
-        loop {
+        while reached & self.goals[p] == 0 {
+            let front = fronts[steps];
+            let next = (front & n) << s | (front & e) << 1 | (front & so) >> s | (front & w) >> 1;
+            if next & !reached == 0 { return None };
             steps += 1;
-            let mut new_front = Vec::new();
-            for [x, y] in front {
-                //look up
-                if self.step_possible([x, y], 1) && !found.contains(&[x, y+1]) {
-                    if y+1 == g { return Some(steps) };
-                    found.insert([x, y+1]);
-                    new_front.push([x, y+1]);
-                };
-                //look right
-                if self.step_possible([x, y], 2) && !found.contains(&[x+1, y]) {
-                    found.insert([x+1, y]);
-                    new_front.push([x+1, y]);
-                };//look down
-                if self.step_possible([x, y], 3) && !found.contains(&[x, y-1]) {
-                    if y-1 == g { return Some(steps) };
-                    found.insert([x, y-1]);
-                    new_front.push([x, y-1]);
-                };//look left
-                if self.step_possible([x, y], 4) && !found.contains(&[x-1, y]) {
-                    found.insert([x-1, y]);
-                    new_front.push([x-1, y]);
-                };
+            fronts[steps] = next & !reached;
+            reached |= next;
+        }
+
+        let mut path = [0; 4];
+        let goal = fronts[steps] & self.goals[p];
+        let mut square = goal & goal.wrapping_neg();
+        for k in (0..steps).rev() {
+            let before = [(square >> s) & n, (square >> 1) & e, (square << s) & so, (square << 1) & w];
+            let (d, from) = before.iter().enumerate().find(|(_, from)| *from & fronts[k] != 0)?;
+            path[d] |= from & fronts[k];
+            square = from & fronts[k];
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use crate::bots::xorshift;
+
+    fn _random_games(games: usize, mut on_move: impl FnMut(&mut Board, &Move)) {
+        //random games on every size with two and four players, calling on_move before every move is made.
+        //every second move is a wall if there is one, so the games keep many walls
+        let mut state = 0x9e3779b97f4a7c15;
+        for game in 0..games {
+            let size = MIN_SIZE + 2 * (game % ((MAX_SIZE - MIN_SIZE) / 2 + 1));
+            let mut b = Board::with_players(size, if game % 3 == 0 { 4 } else { 2 });
+            while !b.is_over() && b.move_sequence.len() < 60 {
+                let moves = b.all_legal_moves();
+                let walls: Vec<&Move> = moves.iter().filter(|m| matches!(m, Wall { .. })).collect();
+                let r = xorshift(&mut state) as usize;
+                let m = if r.is_multiple_of(2) && !walls.is_empty() { *walls[r / 2 % walls.len()] } else { moves[r / 2 % moves.len()] };
+                on_move(&mut b, &m);
+                b.mv(&m);
             }
-            front = new_front;
-            if front.is_empty() { return None };
         }
     }
-}
\ No newline at end of file
+
+    fn _bfs_dist(b: &Board, p: usize) -> Option<usize> {
+        //breadth first search from square to square, reading the walls themselves instead of the open steps
+        let mut dist = vec![vec![None; b.size]; b.size];
+        let mut queue = VecDeque::from([b.players[p]]);
+        dist[b.players[p][0]][b.players[p][1]] = Some(0);
+        while let Some(square) = queue.pop_front() {
+            let d = dist[square[0]][square[1]].unwrap();
+            if b.is_goal(p, square) { return Some(d) };
+            for dir in Direction::ALL {
+                let [x, y] = square;
+                let on_board = match dir { North => y+1 < b.size, East => x+1 < b.size, South => y > 0, West => x > 0 };
+                if !on_board || b.blocked_by_wall(square, dir) { continue };
+                let [x, y] = Board::neighbour(square, dir);
+                if dist[x][y].is_none() {
+                    dist[x][y] = Some(d+1);
+                    queue.push_back([x, y]);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn flood_fill_matches_bfs() {
+        _random_games(200, |b, _| {
+            for p in 0..b.num_players {
+                assert_eq!(b.dist_to_goal(p), _bfs_dist(b, p));
+            }
+        });
+    }
+
+    #[test]
+    fn wall_checks_match_bfs() {
+        //check_move skips the search for walls not cutting a remembered path, it has to agree with searching every time.
+        //the walls are checked in the same order as by all_legal_moves, so the remembered paths carry over
+        _random_games(100, |b, _| {
+            let tm = b.to_move.index();
+            for i in 0..=1 {
+                for x in 0..b.size-1 {
+                    for y in 0..b.size-1 {
+                        let mut placed = b.clone();
+                        placed.place_wall(i, x, y);
+                        let legal = b.walls_left[tm] > 0 && b.wall_fits(i, x, y)
+                            && (0..b.num_players).all(|p| _bfs_dist(&placed, p).is_some());
+                        assert_eq!(b.check_move(&Board::wall(i, x, y)).is_ok(), legal);
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn incremental_hash() {
+        _random_games(200, |b, m| {
+            assert_eq!(b.hash, b.compute_hash());
+            b.mv(m);
+            assert_eq!(b.hash, b.compute_hash());
+            b.unmake(m);
+            assert_eq!(b.hash, b.compute_hash());
+        });
+    }
+
+    #[test]
+    fn position_strings_round_trip() {
+        _random_games(100, |b, _| {
+            let s = b.to_position_string();
+            let read = Board::from_position_string(&s).unwrap();
+            assert_eq!(read.hash, b.hash);
+            assert_eq!(read.to_position_string(), s);
+        });
+    }
+
+    #[test]
+    fn rejected_position_strings() {
+        let ok = |s: &str| Board::from_position_string(s).is_ok();
+        assert!(ok("9 / e1 e9 / 10 10 / 1 / d3h f3h d4v"));
+        assert!(ok("5 / c1 a3 c5 e3 / 2 2 2 2 / 3 /"));
+        //the same wall twice, overlapping walls and crossing walls
+        assert!(!ok("9 / e1 e9 / 10 10 / 1 / d3h d3h"));
+        assert!(!ok("9 / e1 e9 / 10 10 / 1 / d3h e3h d3v"));
+        assert!(!ok("9 / e1 e9 / 10 10 / 1 / d3v d4v"));
+        assert!(!ok("9 / e1 e9 / 10 10 / 1 / d3h d3v"));
+        assert!(!ok("9 / e1 e9 / 10 10 / 1 / i3h"));
+        //pawns off the board, on the same square or missing
+        assert!(!ok("9 / e1 j9 / 10 10 / 1 /"));
+        assert!(!ok("9 / e1 e10 / 10 10 / 1 /"));
+        assert!(!ok("9 / e5 e5 / 10 10 / 1 /"));
+        assert!(!ok("9 / e1 / 10 / 1 /"));
+        assert!(!ok("9 / e1 e9 a5 / 10 10 10 / 1 /"));
+        //more walls left than at the start, a player to move that does not exist and a closed path
+        assert!(!ok("5 / c1 c5 / 31 31 / 1 /"));
+        assert!(!ok("9 / e1 e9 / 11 10 / 1 /"));
+        assert!(!ok("9 / e1 e9 / 10 10 / 3 /"));
+        assert!(!ok("3 / b1 b3 / 2 2 / 1 / a1h b1v"));
+    }
+}
@@ -0,0 +1,113 @@
+//contains weak players without a search, to test the engines against and as easy opponents
+
+use crate::Board;
+use crate::enums::{ Move, Move::*, Direction, Turn::* };
+use crate::engine::{ Engine, SearchLimits, SearchResult };
+
+use std::time::SystemTime;
+
+//plays a uniformly random legal move
+pub struct Random {
+	state: u64,
+}
+
+impl Random {
+	pub fn new() -> Random {
+		let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(1);
+		Random { state: seed | 1 }
+	}
+}
+
+impl Default for Random {
+	fn default() -> Random {
+		Random::new()
+	}
+}
+
+impl Engine for Random {
+	fn name(&self) -> &'static str {
+		"random"
+	}
+
+	fn best_move(&mut self, board: &Board, _limits: SearchLimits) -> SearchResult {
+		let moves = board.clone().all_legal_moves();
+		let r = xorshift(&mut self.state) as usize;
+		let best = if moves.is_empty() { None } else { Some(moves[r % moves.len()]) };
+		SearchResult::single(best, 1)
+	}
+}
+
+//steps along the shortest path to the goal and never places walls
+pub struct Greedy;
+
+impl Engine for Greedy {
+	fn name(&self) -> &'static str {
+		"greedy"
+	}
+
+	fn best_move(&mut self, board: &Board, _limits: SearchLimits) -> SearchResult {
+		SearchResult::single(greedy_step(&mut board.clone()), 1)
+	}
+}
+
+//places the wall lengthening the path of the next player the most, and steps like Greedy once no wall does
+pub struct WallSpammer;
+
+impl Engine for WallSpammer {
+	fn name(&self) -> &'static str {
+		"walls"
+	}
+
+	fn best_move(&mut self, board: &Board, _limits: SearchLimits) -> SearchResult {
+		let mut b = board.clone();
+		let mut nodes = 1;
+		let (tm, opponent) = b.to_move_indices();
+		let (own, theirs) = (b.dist_to_goal(tm).unwrap_or(0), b.dist_to_goal(opponent).unwrap_or(0));
+		//the largest increase of the opponent's path, then the smallest increase of the own path
+		let mut best: Option<(usize, usize, Move)> = None;
+		for m in b.all_legal_moves().into_iter().filter(|m| matches!(m, Wall { .. })) {
+			b.mv(&m);
+			let gain = b.dist_to_goal(opponent).unwrap_or(0) - theirs;
+			let loss = b.dist_to_goal(tm).unwrap_or(0) - own;
+			b.unmake(&m);
+			nodes += 1;
+			if gain > 0 && best.is_none_or(|(g, l, _)| gain > g || gain == g && loss < l) { best = Some((gain, loss, m)) };
+		}
+		let best = best.map(|(_, _, m)| m).or_else(|| greedy_step(&mut b));
+		SearchResult::single(best, nodes)
+	}
+}
+
+pub fn greedy_step(b: &mut Board) -> Option<Move> {
+	//the legal pawn move leaving the player to move closest to the goal, the first one found of equally close moves
+	let tm = b.to_move_indices().0;
+	let mut best: Option<(usize, Move)> = None;
+	for d in Direction::ALL {
+		for m in [Step(d), Jump(d), DiagonalJump(d, Left), DiagonalJump(d, Right)] {
+			if b.check_pawn_move(&m).is_err() { continue };
+			b.mv(&m);
+			let dist = b.dist_to_goal(tm).unwrap_or(usize::MAX);
+			b.unmake(&m);
+			if best.is_none_or(|(best_dist, _)| dist < best_dist) { best = Some((dist, m)) };
+		}
+	}
+	best.map(|(_, m)| m)
+}
+
+pub fn xorshift(state: &mut u64) -> u64 {
+	//the next number of a xorshift generator, a fast random number generator. state must not be 0
+	*state ^= *state << 13;
+	*state ^= *state >> 7;
+	*state ^= *state << 17;
+	*state
+}
+
+pub fn mostly_pawn_move(moves: &[Move], state: &mut u64) -> Option<Move> {
+	//a random move of moves, ordered like all_legal_moves. Three times in four a pawn move if there is one,
+	//so random games do not throw away their walls
+	let pawn_moves = moves.iter().take_while(|m| !matches!(m, Wall { .. })).count();
+	let r = xorshift(state) as usize;
+	if moves.is_empty() { return None };
+	let i = if !r.is_multiple_of(4) && pawn_moves != 0 { r / 4 % pawn_moves } else { r / 4 % moves.len() };
+	Some(moves[i])
+}
@@ -1,183 +1,168 @@
 use crate::Board;
-use crate::enums::{ Notation, Move, Move::*, Player::* };
+use crate::enums::{ Notation, Move, Player::* };
 use crate::helpers::string_from_move;
+use crate::tt::{ TranspositionTable, Entry, Bound };
+use crate::eval::{ Weights, evaluate, WIN, MIN_WIN };
+use crate::engine::{ Engine, SearchLimits };
 
-use std::time::SystemTime;
+use std::time::{ SystemTime, Instant, Duration };
+use std::sync::atomic::{ AtomicU64, AtomicBool, Ordering::Relaxed };
 
 use rayon::prelude::*;
 
+//the board a search thread works on. Moves are made and taken back on it, it is never copied inside the search
 #[derive(Clone)]
 pub struct GameState {
 	pub board: Board,
-	pub mv_from_parent: Option<Move>,
+	pub weights: Weights,
 }
 
-pub fn brute_force(mut board: Board, max_depth: usize, notation: Notation) {
-	let begin_time = SystemTime::now();
+//the deepest iteration the search will ever start. Only reachable with a time limit
+pub const MAX_DEPTH: usize = 64;
 
-	let s;
-	match board.to_move {
-		Player1 => s = 1,
-		Player2 => s = -1,
-	}
+#[derive(Clone)]
+pub struct SearchInfo {
+	pub depth: usize,
+	pub score: isize,              //from the point of view of player 1, like GameState::score
+	pub line: Vec<Move>,
+	pub nodes: u64,
+}
 
-	let res: Result<Vec<(Vec<Move>, isize)>, (Vec<Move>, isize)> =
-		board
-			.all_legal_moves()
-			.par_iter()
-			.map(|mv|
-				_brute_force_recursive_dfs(
-				GameState {
-					board: board.mv_new_no_memory(mv),
-					mv_from_parent: Some(mv.clone()),
-				},
-				1,
-				max_depth))
-			.collect();
+struct SearchContext<'a> {
+	tt: &'a TranspositionTable,
+	deadline: Option<Instant>,
+	nodes: AtomicU64,
+	stopped: AtomicBool,
+}
 
-	let (seq, score);
-	match res {
-		Ok(v) => (seq, score) = v.into_iter().max_by_key(|(_seq, score)| s*score).unwrap(),
-		Err((se, sc)) => (seq, score) = (se, sc),
+impl SearchContext<'_> {
+	fn out_of_time(&self) -> bool {
+		//counts the node and checks the deadline. Once the deadline passed every thread stops
+		self.nodes.fetch_add(1, Relaxed);
+		if self.stopped.load(Relaxed) { return true };
+		if self.deadline.is_some_and(|d| Instant::now() >= d) {
+			self.stopped.store(true, Relaxed);
+			return true;
+		}
+		false
 	}
+}
 
-	let total_time = SystemTime::now().duration_since(begin_time).unwrap();
-	let millis = total_time.as_millis();
-	let secs = millis / 1000;
-	let mins = secs / 60;
-	let hours = mins / 60;
-
-	print!("    That took about ");
+pub fn brute_force(engine: &mut dyn Engine, board: &Board, max_depth: usize, notation: Notation) {
+	//searches up to a fixed depth. Prints the best line of every completed depth, if the engine searches by depth
+	let begin_time = SystemTime::now();
 
-	if hours != 0 {
-		print!("{} Hours ", hours);
-	}
-	if mins != 0 || hours != 0 {
-		print!("{} Minutes ", mins % 60);
-	}
-	if (secs != 0 || mins!= 0) && hours == 0 {
-		print!("{} Seconds ", secs%60);
-	}
-	if secs < 15 {
-		print!("{} Milliseconds", millis%1000);
+	let res = engine.best_move(board, SearchLimits::depth(max_depth));
+	for info in &res.iterations {
+		_print_iteration(board, info, notation);
 	}
 
+	_print_time_since(begin_time);
 
 	print!("\n    Best sequence found:    ");
-	for m in seq.iter().rev() {
-		print!("{} ", string_from_move(&m, board.players[board.to_move_indices().0], notation));
-	}
-	println!("");
+	_print_line(board, &res.line, notation);
+	println!();
 
-	println!("    Score is {}", score);
+	if let Some(score) = res.score { println!("    Score is {}", score) };
 }
 
-fn _brute_force_recursive_dfs(
-mut game: GameState,
-depth: usize,
-max_depth: usize) -> Result<(Vec<Move>, isize), (Vec<Move>, isize)> {
+pub fn think(engine: &mut dyn Engine, board: &Board, seconds: usize, notation: Notation) {
+	//like brute_force, but searches until the time is up instead of up to a fixed depth
+	let begin_time = SystemTime::now();
 
-	if depth == max_depth || game.board.current_player_wins() {
-		return Ok((vec![game.mv_from_parent.clone().unwrap()], game.score()));
+	let res = engine.best_move(board, SearchLimits::seconds(seconds));
+	for info in &res.iterations {
+		_print_iteration(board, info, notation);
 	}
 
-	let s;
-	match game.board.to_move {
-		Player1 => s = 1,
-		Player2 => s = -1,
-	}
+	_print_time_since(begin_time);
 
-	let res: Result<Vec<(Vec<Move>, isize)>, (Vec<Move>, isize)> =
-		game.board
-			.all_legal_moves()
-			.par_iter()
-			.map(|mv|
-				_brute_force_recursive_dfs(
-				GameState {
-					board: game.board.mv_new_no_memory(mv),
-					mv_from_parent: Some(mv.clone()),
-				},
-				depth+1,
-				max_depth))
-			.collect();
-	let (mut seq, score);
-	match res {
-		Ok(v) => (seq, score) = v.into_iter().max_by_key(|(_seq, score)| s*score).unwrap(),
-		Err((se, sc)) => (seq, score) = (se, sc),
-	}
-
-	seq.push(game.mv_from_parent.clone().unwrap());
-
-	match (game.board.to_move == Player1 && score == -1000) || (game.board.to_move == Player2 && score == 1000) {
-		true => Err((seq, score)),
-		false => Ok((seq, score)),
+	match res.best {
+		Some(m) => println!("\n    Best move found:    {}", string_from_move(&m, board, notation)),
+		None => println!("\n    No move found"),
 	}
+	if res.depth != 0 { println!("    Depth reached: {}, nodes searched: {}", res.depth, res.nodes) };
+	if let Some(score) = res.score { println!("    Score is {}", score) };
 }
 
-impl GameState {
-	fn score(&self) -> isize {
-		match self.board.to_move {
-			Player1 => if self.board.current_player_wins() { return 1000 },
-			Player2 => if self.board.current_player_wins() { return -1000 },
-		}
-		let mut sum: isize = 0;
-		sum += self.board.walls_left[0] as isize - self.board.walls_left[1] as isize;
-		sum += 2*(self.board.dist_to_goal(1).unwrap() as isize - self.board.dist_to_goal(0).unwrap() as isize);
-		if self.board.to_move == Player1 { sum += 1 } else { sum -= 1 };
-		sum
+pub fn reply(engine: &mut dyn Engine, board: &Board, seconds: usize, notation: Notation) -> Option<Move> {
+	//picks the engine's move when playing against it. Only prints the chosen move
+	let res = engine.best_move(board, SearchLimits::seconds(seconds));
+	let m = res.best?;
+	match res.score {
+		Some(score) => println!("    Engine plays {}    (depth {}, score {})", string_from_move(&m, board, notation), res.depth, score),
+		None => println!("    Engine plays {}", string_from_move(&m, board, notation)),
 	}
-
-	fn score_5x5(&self) -> isize {
-		match self.board.to_move {
-			Player1 => if self.board.current_player_wins_5x5() { return 1000 },
-			Player2 => if self.board.current_player_wins_5x5() { return -1000 },
-		}
-		let mut sum: isize = 0;
-		sum += self.board.walls_left[0] as isize - self.board.walls_left[1] as isize;
-		sum += 2*(self.board.dist_to_goal(1).unwrap() as isize - self.board.dist_to_goal(0).unwrap() as isize);
-		if self.board.to_move == Player1 { sum += 1 } else { sum -= 1 };
-		sum
-	}
-
+	Some(m)
 }
 
-fn filter_5x5(mv: &Move) -> bool {
-	match mv {
-		Step(_) | Wall([_, 2..=5, 2..=5]) => true,
-		_ => false,
+//positions searched by bench, from the start to late in the game
+const BENCH_POSITIONS: [&str; 5] = [
+	"9 / e1 e9 / 10 10 / 1 /",
+	"9 / e3 e7 / 8 8 / 1 / c3h f3h d6v e6h",
+	"9 / d5 f5 / 5 6 / 2 / a3h c3h e3h g6h b6v d7h f7v h4v c5h",
+	"9 / c7 g2 / 3 2 / 1 / a2h c2h e2v f5h h5h a7h c7v d4h e4v f7h b5v g3v h2h e8h f3v",
+	"7 / d2 d6 / 5 5 / 1 / c3h d4v",
+];
+
+pub fn bench(depth: usize) {
+	//searches every bench position with an empty transposition table and the default weights, and prints the node throughput
+	let mut total_nodes = 0;
+	let mut total_time = Duration::ZERO;
+	for (i, position) in BENCH_POSITIONS.iter().enumerate() {
+		let board = Board::from_position_string(position).unwrap();
+		let tt = TranspositionTable::new(20);
+		let begin_time = Instant::now();
+		let res = iterative_deepening(&board, depth, None, &tt, &Weights::default(), &mut |_| ());
+		let time = begin_time.elapsed();
+		println!("    Position {}:    {:>10} nodes in {:>6} ms", i+1, res.nodes, time.as_millis());
+		total_nodes += res.nodes;
+		total_time += time;
 	}
+	println!("    Total:         {:>10} nodes in {:>6} ms, {:.0} nodes per second",
+		total_nodes, total_time.as_millis(), total_nodes as f64 / total_time.as_secs_f64());
 }
 
-pub fn brute_force_5x5(mut board: Board, max_depth: usize, notation: Notation) {
-	let begin_time = SystemTime::now();
-	
-	let s;
-	match board.to_move {
-		Player1 => s = 1,
-		Player2 => s = -1,
+pub fn iterative_deepening(
+board: &Board,
+max_depth: usize,
+deadline: Option<Instant>,
+tt: &TranspositionTable,
+weights: &Weights,
+on_iteration: &mut dyn FnMut(&SearchInfo)) -> SearchInfo {
+	//searches depth 1, 2, ... max_depth and returns the result of the deepest completed iteration.
+	//the first iteration always completes, so there is a move even if the deadline is already over
+
+	//the search only handles two player games, player 2 is the opponent of player 1
+	let s = match board.to_move {
+		Player1 => 1,
+		_ => -1,
+	};
+
+	let mut game = GameState { board: board.clone(), weights: *weights };
+	game.board.move_sequence.clear();
+
+	let mut ctx = SearchContext { tt, deadline: None, nodes: AtomicU64::new(0), stopped: AtomicBool::new(false) };
+	let mut res = SearchInfo { depth: 0, score: s*game.score_to_move(), line: vec![], nodes: 0 };
+	for depth in 1..=max_depth {
+		let (score, line) = _search_root(&mut game, depth, &ctx);
+		if ctx.stopped.load(Relaxed) { break };
+		res = SearchInfo { depth, score: s*score, line, nodes: ctx.nodes.load(Relaxed) };
+		on_iteration(&res);
+		if score.abs() >= MIN_WIN { break };
+		ctx.deadline = deadline;
 	}
+	res.nodes = ctx.nodes.load(Relaxed);
+	res
+}
 
-	let res: Result<Vec<(Vec<Move>, isize)>, (Vec<Move>, isize)> = 
-		board
-			.all_legal_moves()
-			.par_iter()
-			.filter(|mv| filter_5x5(mv))
-			.map(|mv|
-				_brute_force_recursive_dfs_5x5(
-				GameState {
-					board: board.mv_new_no_memory(mv),
-					mv_from_parent: Some(mv.clone()),
-				},
-				1,
-				max_depth))
-			.collect();
-
-	let (seq, score);
-	match res {
-		Ok(v) => (seq, score) = v.into_iter().max_by_key(|(_seq, score)| s*score).unwrap(),
-		Err((se, sc)) => (seq, score) = (se, sc),
-	}
+fn _print_iteration(board: &Board, info: &SearchInfo, notation: Notation) {
+	print!("    Depth {:>2}:    ", info.depth);
+	_print_line(board, &info.line, notation);
+	println!("   (score {})", info.score);
+}
 
+fn _print_time_since(begin_time: SystemTime) {
 	let total_time = SystemTime::now().duration_since(begin_time).unwrap();
 	let millis = total_time.as_millis();
 	let secs = millis / 1000;
@@ -190,64 +175,188 @@ pub fn brute_force_5x5(mut board: Board, max_depth: usize, notation: Notation) {
 		print!("{} Hours ", hours);
 	}
 	if mins != 0 || hours != 0 {
-		print!("{} Minutes ", mins%60);
+		print!("{} Minutes ", mins % 60);
 	}
 	if (secs != 0 || mins!= 0) && hours == 0 {
-		print!("{} Seconds ", secs&60);
+		print!("{} Seconds ", secs%60);
 	}
 	if secs < 15 {
 		print!("{} Milliseconds", millis%1000);
 	}
+}
 
+fn _print_line(board: &Board, seq: &[Move], notation: Notation) {
+	//replays the line so every move is printed from the position it is played in
+	let mut b = board.clone();
+	for m in seq {
+		print!("{} ", string_from_move(m, &b, notation));
+		b.mv(m);
+	}
+}
 
-	print!("\n    Best sequence found:    ");
-	for m in seq.iter().rev() {
-		print!("{} ", string_from_move(&m, board.players[board.to_move_indices().0], notation));
+fn _extend_line(board: &Board, seq: &mut Vec<Move>, depth: usize, tt: &TranspositionTable) {
+	//lines cut short by transposition table hits are continued along the stored best moves
+	let mut b = board.clone();
+	for m in seq.iter() {
+		b.mv(m);
 	}
-	println!("");
+	while seq.len() < depth && !b.is_over() {
+		let Some(Entry { best: Some(m), .. }) = tt.probe(b.hash) else { break };
+		if !b.all_legal_moves().contains(&m) { break };
+		b.mv(&m);
+		seq.push(m);
+	}
+}
 
-	println!("    Score is {}", score);
+fn _ordered_moves(board: &mut Board, hint: Option<&Move>) -> Vec<Move> {
+	//legal moves, with the best move stored in the transposition table searched first
+	let mut moves = board.all_legal_moves();
+	if let Some(h) = hint {
+		if let Some(i) = moves.iter().position(|m| m == h) {
+			let m = moves.remove(i);
+			moves.insert(0, m);
+		}
+	}
+	moves
 }
 
-fn _brute_force_recursive_dfs_5x5(
-mut game: GameState,
-depth: usize,
-max_depth: usize) -> Result<(Vec<Move>, isize), (Vec<Move>, isize)> {
+fn _search_root(game: &mut GameState, depth: usize, ctx: &SearchContext) -> (isize, Vec<Move>) {
+	//searches the first move alone to get a bound, then the remaining root moves in parallel.
+	//scores are from the point of view of the player to move
+	let hint = ctx.tt.probe(game.board.hash).and_then(|e| e.best);
+	let moves = _ordered_moves(&mut game.board, hint.as_ref());
+	let Some((first, rest)) = moves.split_first() else {
+		return (game.score_to_move(), vec![]);
+	};
+
+	game.board.mv(first);
+	let (score, mut seq) = _alpha_beta(game, depth-1, -WIN, WIN, 1, ctx);
+	game.board.unmake(first);
+	let (mut best_score, mut best_seq) = (-score, {seq.insert(0, *first); seq});
+
+	//nothing beats winning with the first move
+	if best_score != WIN-1 {
+		//every thread gets its own copy of the board
+		let alpha = best_score;
+		let results: Vec<(isize, Vec<Move>)> = rest
+			.par_iter()
+			.map_init(|| game.clone(), |game, mv| {
+				game.board.mv(mv);
+				let (score, mut seq) = _alpha_beta(game, depth-1, -WIN, -alpha, 1, ctx);
+				game.board.unmake(mv);
+				seq.insert(0, *mv);
+				(-score, seq)
+			})
+			.collect();
 
-	if depth == max_depth || game.board.current_player_wins_5x5() {
-		return Ok((vec![game.mv_from_parent.clone().unwrap()], game.score_5x5()));
+		for (score, seq) in results {
+			if score > best_score {
+				(best_score, best_seq) = (score, seq);
+			}
+		}
 	}
 
-	let s;
-	match game.board.to_move {
-		Player1 => s = 1,
-		Player2 => s = -1,
+	if ctx.stopped.load(Relaxed) { return (best_score, best_seq) };
+	ctx.tt.store(game.board.hash, &Entry { depth, score: best_score, bound: Bound::Exact, best: best_seq.first().copied() });
+	_extend_line(&game.board, &mut best_seq, depth, ctx.tt);
+	(best_score, best_seq)
+}
+
+fn _alpha_beta(game: &mut GameState, depth: usize, mut alpha: isize, mut beta: isize, ply: usize, ctx: &SearchContext) -> (isize, Vec<Move>) {
+	//negamax with alpha-beta pruning. Returns the score for the player to move and the principal variation.
+	//ply counts the moves made since the root, a win ply moves ahead scores WIN - ply.
+	//once the search is stopped the returned values are meaningless and nothing is stored
+	if ctx.out_of_time() {
+		return (0, vec![]);
+	}
+	if game.board.is_over() {
+		return (game.score_to_move().signum() * (WIN - ply as isize), vec![]);
+	}
+	if depth == 0 {
+		return (game.score_to_move(), vec![]);
 	}
 
-	let res: Result<Vec<(Vec<Move>, isize)>, (Vec<Move>, isize)> = 
-		game.board
-			.all_legal_moves()
-			.par_iter()
-			.filter(|mv| filter_5x5(mv))
-			.map(|mv|
-				_brute_force_recursive_dfs_5x5(
-				GameState {
-					board: game.board.mv_new_no_memory(mv),
-					mv_from_parent: Some(mv.clone()),
-				},
-				depth+1,
-				max_depth))
-			.collect();
-	let (mut seq, score);
-	match res {
-		Ok(v) => (seq, score) = v.into_iter().max_by_key(|(_seq, score)| s*score).unwrap(),
-		Err((se, sc)) => (seq, score) = (se, sc),
+	let entry = ctx.tt.probe(game.board.hash).map(|e| Entry { score: _score_from_tt(e.score, ply), ..e });
+	if let Some(e) = &entry {
+		if e.depth >= depth {
+			match e.bound {
+				Bound::Exact => return (e.score, vec![]),
+				Bound::Lower => alpha = alpha.max(e.score),
+				Bound::Upper => beta = beta.min(e.score),
+			}
+			if alpha >= beta { return (e.score, vec![]) };
+		}
+	}
+	let alpha_orig = alpha;
+
+	let mut best = (-WIN, vec![]);
+	for mv in _ordered_moves(&mut game.board, entry.and_then(|e| e.best).as_ref()) {
+		game.board.mv(&mv);
+		let (score, mut seq) = _alpha_beta(game, depth-1, -beta, -alpha, ply+1, ctx);
+		game.board.unmake(&mv);
+		if ctx.stopped.load(Relaxed) { return (0, vec![]) };
+		let score = -score;
+		if score > best.0 || best.1.is_empty() {
+			seq.insert(0, mv);
+			best = (score, seq);
+		}
+		if score > alpha { alpha = score };
+		if alpha >= beta { break };
+	}
+
+	let bound = if best.0 <= alpha_orig {
+		Bound::Upper
+	} else if best.0 >= beta {
+		Bound::Lower
+	} else {
+		Bound::Exact
+	};
+	ctx.tt.store(game.board.hash, &Entry { depth, score: _score_to_tt(best.0, ply), bound, best: best.1.first().copied() });
+	best
+}
+
+fn _score_to_tt(score: isize, ply: usize) -> isize {
+	//wins are stored counted from the position, not from the root, so they stay right when the position is reached at another ply
+	if score >= MIN_WIN { score + ply as isize } else if score <= -MIN_WIN { score - ply as isize } else { score }
+}
+
+fn _score_from_tt(score: isize, ply: usize) -> isize {
+	if score >= MIN_WIN { score - ply as isize } else if score <= -MIN_WIN { score + ply as isize } else { score }
+}
+
+impl GameState {
+	fn score_to_move(&self) -> isize {
+		match self.board.to_move {
+			Player1 => self.score(),
+			_ => -self.score(),
+		}
 	}
 
-	seq.push(game.mv_from_parent.clone().unwrap());
+	fn score(&self) -> isize {
+		evaluate(&self.board, &self.weights).total
+	}
+
+}
 
-	match (game.board.to_move == Player1 && score == -1000) || (game.board.to_move == Player2 && score == 1000) {
-		true => Err((seq, score)),
-		false => Ok((seq, score)),
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fastest_win() {
+		//player 1 wins in two moves and player 2 in four. Every win scores less the further away it is,
+		//also when the transposition table is kept from an earlier search
+		let board = Board::from_position_string("9 / e7 a5 / 0 0 / 1 /").unwrap();
+		let tt = TranspositionTable::new(16);
+		for depth in [5, 3, 5] {
+			let res = iterative_deepening(&board, depth, None, &tt, &Weights::default(), &mut |_| ());
+			assert_eq!(res.score, WIN-3);
+			assert_eq!(res.line.first(), Some(&Move::Step(crate::enums::Direction::North)));
+		}
+		let mut b = board.clone();
+		b.extend_no_check("e8", crate::enums::Notation::Absolute);
+		let res = iterative_deepening(&b, 4, None, &tt, &Weights::default(), &mut |_| ());
+		assert_eq!(res.score, WIN-2);
 	}
 }
@@ -0,0 +1,100 @@
+//contains the engine trait, the common interface of everything that picks moves.
+//'brute', 'think' and 'play' use whichever engine was picked with the 'engine' command
+
+use crate::Board;
+use crate::enums::{ Move, EngineKind };
+use crate::brute::{ iterative_deepening, SearchInfo, MAX_DEPTH };
+use crate::mcts::mcts;
+use crate::bots::{ Random, Greedy, WallSpammer };
+use crate::eval::Weights;
+use crate::tt::TranspositionTable;
+
+use std::time::{ Instant, Duration };
+
+//mcts has no depth, a depth limit gives it this many playouts per move of depth
+const PLAYOUTS_PER_DEPTH: usize = 10000;
+
+//how long an engine may search. Engines stop at whichever limit comes first, a search without limits never ends
+#[derive(Clone, Copy, Default)]
+pub struct SearchLimits {
+	pub depth: Option<usize>,
+	pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+	pub fn depth(depth: usize) -> SearchLimits {
+		SearchLimits { depth: Some(depth), time: None }
+	}
+
+	pub fn seconds(seconds: usize) -> SearchLimits {
+		SearchLimits { depth: None, time: Some(Duration::from_secs(seconds as u64)) }
+	}
+
+	fn deadline(&self) -> Option<Instant> {
+		self.time.map(|t| Instant::now() + t)
+	}
+}
+
+pub struct SearchResult {
+	pub best: Option<Move>,               //None if there is no legal move
+	pub score: Option<isize>,             //from the point of view of player 1, None for engines that do not score positions
+	pub depth: usize,                     //of the deepest completed iteration, 0 for engines that do not search by depth
+	pub nodes: u64,                       //positions searched, or playouts played by mcts
+	pub line: Vec<Move>,                  //the expected continuation, starting with the best move
+	pub iterations: Vec<SearchInfo>,      //every completed depth of an iterative deepening search
+}
+
+impl SearchResult {
+	pub fn single(best: Option<Move>, nodes: u64) -> SearchResult {
+		//the result of an engine that only picks a move
+		SearchResult { best, score: None, depth: 0, nodes, line: best.into_iter().collect(), iterations: vec![] }
+	}
+}
+
+pub trait Engine {
+	fn name(&self) -> &'static str;
+	fn best_move(&mut self, board: &Board, limits: SearchLimits) -> SearchResult;
+}
+
+pub fn new_engine<'a>(kind: EngineKind, tt: &'a TranspositionTable, weights: &Weights) -> Box<dyn Engine + 'a> {
+	//the alpha-beta search shares the transposition table of the instance, so it is kept between searches
+	match kind {
+		EngineKind::AlphaBeta => Box::new(AlphaBeta { tt, weights: *weights }),
+		EngineKind::Mcts => Box::new(Mcts),
+		EngineKind::Random => Box::new(Random::new()),
+		EngineKind::Greedy => Box::new(Greedy),
+		EngineKind::WallSpammer => Box::new(WallSpammer),
+	}
+}
+
+pub struct AlphaBeta<'a> {
+	pub tt: &'a TranspositionTable,
+	pub weights: Weights,
+}
+
+impl Engine for AlphaBeta<'_> {
+	fn name(&self) -> &'static str {
+		"alphabeta"
+	}
+
+	fn best_move(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+		let mut iterations = vec![];
+		let res = iterative_deepening(board, limits.depth.unwrap_or(MAX_DEPTH), limits.deadline(), self.tt, &self.weights,
+			&mut |info| iterations.push(info.clone()));
+		SearchResult { best: res.line.first().copied(), score: Some(res.score), depth: res.depth, nodes: res.nodes, line: res.line, iterations }
+	}
+}
+
+pub struct Mcts;
+
+impl Engine for Mcts {
+	fn name(&self) -> &'static str {
+		"mcts"
+	}
+
+	fn best_move(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+		let playouts = limits.depth.map_or(usize::MAX, |d| d.max(1) * PLAYOUTS_PER_DEPTH);
+		let stats = mcts(board, playouts, limits.deadline());
+		SearchResult::single(stats.first().map(|s| s.mv), stats.iter().map(|s| s.visits as u64).sum())
+	}
+}
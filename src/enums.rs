@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 #[derive(Clone, Copy)]
 pub enum Color {
     Empty,
@@ -7,24 +9,84 @@ pub enum Color {
 //    Blue,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Move {
-    Step(usize),
-    Wall([usize; 3]),
+    Step(Direction),                    //to the square next to the pawn
+    Jump(Direction),                    //straight over the pawn next to it
+    DiagonalJump(Direction, Turn),      //towards the pawn next to it, then sideways
+    Wall { orientation: Orientation, x: usize, y: usize },    //centred on the north east corner of square x, y
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+impl Direction {
+    //clockwise, so turning right is the next direction
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn turn(self, t: Turn) -> Direction {
+        match t {
+            Turn::Right => Direction::ALL[(self as usize + 1) % 4],
+            Turn::Left => Direction::ALL[(self as usize + 3) % 4],
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self as usize + 2) % 4]
+    }
+
+    pub fn turn_to(self, other: Direction) -> Option<Turn> {
+        //the turn leading from self to other, if they are at a right angle
+        [Turn::Left, Turn::Right].into_iter().find(|t| self.turn(*t) == other)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum Player {
     Player1,
-    Player2
+    Player2,
+    Player3,
+    Player4,
+}
+
+impl Player {
+    pub fn index(&self) -> usize {
+        match self {
+            Player::Player1 => 0,
+            Player::Player2 => 1,
+            Player::Player3 => 2,
+            Player::Player4 => 3,
+        }
+    }
+
+    pub fn from_index(i: usize) -> Player {
+        //panics if i is not 0, 1, 2 or 3
+        [Player::Player1, Player::Player2, Player::Player3, Player::Player4][i]
+    }
 }
 
 pub enum IoState {
     Hello,
     Await,
     Help(Option<String>),
-    NewBoard,
-    NewBoard5x5,
+    NewBoard(usize, usize),
     PlayMoves(String),
     PlayMovesNoCheck(String),
     ShowBoard,
@@ -33,7 +95,30 @@ pub enum IoState {
     Unset(Flag),
     Fill(String),
     Brute(usize),
-    Brute5x5(usize),
+    Think(usize),
+    Bench(usize),
+    Perft(usize),
+    Eval,
+    ShowWeights,
+    SetWeight(String, isize),
+    LoadWeights(String),
+    SaveWeights(String),
+    Tune(usize, usize, String),
+    Mcts(usize),
+    MctsTime(usize),
+    ShowEngine,
+    SetEngine(EngineKind),
+    Match(MatchSettings),
+    Play(Player, usize),
+    EndPlay,
+    EngineMove,
+    Save(String, Option<String>),
+    Load(String, Option<String>),
+    Export(String, Vec<String>),
+    Import(String),
+    ShowPosition,
+    SetPosition(String),
+    Legal(LegalFilter),
     Undo(usize),
     Quit,
 }
@@ -44,12 +129,47 @@ pub enum Notation {
     Absolute,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EngineKind {
+    AlphaBeta,
+    Mcts,
+    Random,
+    Greedy,
+    WallSpammer,
+}
+
+pub struct MatchSettings {
+    pub engines: [(EngineKind, Option<String>); 2],    //the engines and the weights files they use, if not the current weights
+    pub games: usize,
+    pub depth: Option<usize>,
+    pub time: Option<Duration>,                        //per move
+    pub max_moves: usize,                              //games still running after this many moves are draws
+    pub openings: Option<String>,                      //a file of positions to start from, else the start position
+    pub sprt: Option<SprtSettings>,                    //stops the match once the test decided, the games are the most played
+}
+
+#[derive(Clone, Copy)]
+pub struct SprtSettings {
+    pub elo0: f64,                                     //the elo difference of the null hypothesis
+    pub elo1: f64,                                     //the elo difference of the alternative hypothesis, larger than elo0
+    pub alpha: f64,                                    //the chance of accepting elo1 although elo0 is true
+    pub beta: f64,                                     //the chance of accepting elo0 although elo1 is true
+}
+
 #[derive(Eq, Hash, PartialEq, Clone, Copy)]
 pub enum Flag {
     Invert,
 }
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Copy)]
+pub enum LegalFilter {
+    All,
+    Steps,
+    Walls,
+    Near([usize; 2]),   //moves landing on or next to the square and walls touching it
+}
+
+#[derive(Eq, Hash, PartialEq, Debug)]
 pub enum ParseError {
     NoMovesGiven,
     NoFlagGiven,
@@ -61,10 +181,24 @@ pub enum ParseError {
     UnknownCommand,
     InputAfterShow,
     BruteNoDepthGiven,
+    ThinkNoTimeGiven,
+    PerftNoDepthGiven,
+    NoSideGiven,
+    UnknownSide,
+    NoFileGiven,
+    PositionIncorrect,
     InvalidMove,
     NotANumber,
     NotEnoughToUndo,
-    InputAfterNew,
+    InvalidBoardSize,
+    InvalidPlayerCount,
+    UnknownLegalFilter,
+    UnknownWeight,
+    TuneNoGamesGiven,
+    MctsNoIterationsGiven,
+    UnknownEngine,
+    MatchArgumentsIncorrect,
+    SprtArgumentsIncorrect,
 }
 
 #[derive(Eq, Hash, PartialEq, Debug)]
@@ -78,9 +212,28 @@ pub enum MoveError {
     SpaceOccupied,
     P1NoPath,
     P2NoPath,
+    P3NoPath,
+    P4NoPath,
+    WallOutsideBoard,
     GameIsOver,
 }
 
+#[derive(Eq, Hash, PartialEq, Debug)]
+pub enum LoadSaveError {
+    FileNotFound,
+    FileNotWritable,
+    TagNotFound,
+    CorruptedGame,
+    IllegalMoveList(String),
+    UnsupportedBoardSize,
+    InvalidRecordMove(String),
+    IllegalRecordMove(String, MoveError),
+    CorruptedWeights,
+    CorruptedOpenings,
+    TooManyNames,
+    WrongResult,
+}
+
 #[derive(Eq, Hash, PartialEq)]
 pub enum HelpMessage {
     General,
@@ -90,6 +243,23 @@ pub enum HelpMessage {
     Set,
     Unset,
     Brute,
+    Think,
+    Bench,
+    Perft,
+    Eval,
+    Weights,
+    Tune,
+    Mcts,
+    Engine,
+    Match,
+    Sprt,
+    Play,
+    Save,
+    Load,
+    Export,
+    Import,
+    Position,
+    Legal,
     Fill,
     Undo,
     Notation,
@@ -0,0 +1,117 @@
+//contains the static evaluation the search scores positions with, and the weights of its terms.
+//the weights are read from a file at startup and can be changed with the 'weights' command
+
+use crate::Board;
+use crate::enums::{ Player::*, LoadSaveError, LoadSaveError::* };
+
+use std::fs;
+
+//the score of a won position. The search scores a win found n moves ahead as WIN - n, so faster wins score higher
+pub const WIN: isize = 1000;
+//scores at least this large are wins found by the search. Evaluations of positions that are not over stay closer to zero
+pub const MIN_WIN: isize = WIN - 100;
+
+//the weights are read from this file at startup, if it exists
+pub const WEIGHTS_FILE: &str = "weights.cfg";
+//'tune' writes the tuned weights to this file if no file is given
+pub const TUNED_FILE: &str = "tuned.cfg";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weights {
+	pub walls: isize,              //per wall player 1 has left more than player 2
+	pub paths: isize,              //per step player 1 is closer to the goal than player 2
+	pub tempo: isize,              //for player 1 being the player to move
+}
+
+impl Default for Weights {
+	fn default() -> Weights {
+		Weights { walls: 1, paths: 2, tempo: 1 }
+	}
+}
+
+impl Weights {
+	pub const NAMES: [&'static str; 3] = ["walls", "paths", "tempo"];
+
+	pub fn get(&self, name: &str) -> Option<isize> {
+		match name {
+			"walls" => Some(self.walls),
+			"paths" => Some(self.paths),
+			"tempo" => Some(self.tempo),
+			_ => None,
+		}
+	}
+
+	pub fn set(&mut self, name: &str, value: isize) -> Option<()> {
+		match name {
+			"walls" => self.walls = value,
+			"paths" => self.paths = value,
+			"tempo" => self.tempo = value,
+			_ => return None,
+		}
+		Some(())
+	}
+
+	pub fn load(path: &str) -> Result<Weights, LoadSaveError> {
+		//one weight per line, like 'paths: 2'. Weights not in the file keep their default
+		let content = fs::read_to_string(path).map_err(|_| FileNotFound)?;
+		let mut weights = Weights::default();
+		for line in content.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+			let Some((name, value)) = line.split_once(':') else { return Err(CorruptedWeights) };
+			let value = value.trim().parse().map_err(|_| CorruptedWeights)?;
+			weights.set(name.trim(), value).ok_or(CorruptedWeights)?;
+		}
+		Ok(weights)
+	}
+
+	pub fn save(&self, path: &str) -> Result<(), LoadSaveError> {
+		let content: String = Weights::NAMES.iter().map(|name| format!("{}: {}\n", name, self.get(name).unwrap())).collect();
+		fs::write(path, content).map_err(|_| FileNotWritable)
+	}
+}
+
+//the terms of the static evaluation, all from the point of view of player 1
+pub struct Evaluation {
+	pub winner: Option<usize>,
+	pub distances: [usize; 2],
+	pub walls: isize,
+	pub paths: isize,
+	pub tempo: isize,
+	pub total: isize,              //WIN or -WIN once a pawn reached its goal, else the sum of the terms
+}
+
+pub fn evaluate(board: &Board, weights: &Weights) -> Evaluation {
+	let distances = [0, 1].map(|p| board.dist_to_goal(p).unwrap());
+	let winner = board.goal_reached();
+	let walls = weights.walls * (board.walls_left[0] as isize - board.walls_left[1] as isize);
+	let paths = weights.paths * (distances[1] as isize - distances[0] as isize);
+	let tempo = if board.to_move == Player1 { weights.tempo } else { -weights.tempo };
+	let total = match winner {
+		Some(0) => WIN,
+		Some(_) => -WIN,
+		//large weights must not make a position look won
+		None => (walls + paths + tempo).clamp(1-MIN_WIN, MIN_WIN-1),
+	};
+	Evaluation { winner, distances, walls, paths, tempo, total }
+}
+
+pub fn print_eval(board: &Board, weights: &Weights) {
+	//prints every term of the static evaluation of the current position
+	let e = evaluate(board, weights);
+	match e.winner {
+		Some(p) => println!("    Terminal:      player {} reached the goal", p+1),
+		None if board.current_player_wins() => println!("    Terminal:      no, but player {} steps onto the goal next move", board.to_move_indices().0+1),
+		None => println!("    Terminal:      no"),
+	}
+	println!("    Distances:     player 1: {}, player 2: {}", e.distances[0], e.distances[1]);
+	println!("    Walls left:    player 1: {}, player 2: {}", board.walls_left[0], board.walls_left[1]);
+	println!("    Walls term:    {:>5}    {} per wall player 1 has more", e.walls, weights.walls);
+	println!("    Path term:     {:>5}    {} per step player 1 is closer to the goal", e.paths, weights.paths);
+	println!("    Tempo term:    {:>5}    {} for player 1 to move, {} for player 2", e.tempo, weights.tempo, -weights.tempo);
+	println!("    Score:         {:>5}    from the point of view of player 1{}", e.total, if e.winner.is_some() { ", the game is over" } else { "" });
+}
+
+pub fn print_weights(weights: &Weights) {
+	for name in Weights::NAMES {
+		println!("    {:<8}{}", name, weights.get(name).unwrap());
+	}
+}
@@ -2,254 +2,142 @@ use crate::enums::{
 	Notation, Notation::*,
 	IoState, IoState::*,
 	Flag, Flag::*,
+	LegalFilter,
+	EngineKind,
+	MatchSettings,
+	SprtSettings,
 	ParseError, ParseError::*,
 	Move, Move::*,
+	Direction, Direction::*,
+	Orientation, Orientation::*,
+	Player::*,
 	HelpMessage };
 
+use crate::Board;
+use crate::eval::{ Weights, TUNED_FILE };
+
 use std::io;
+use std::time::Duration;
 use std::collections::HashMap;
 
-pub fn move_from_str(s: &str, [a, b]: [usize; 2], [m, n]: [usize; 2], notation: Notation) -> Result<Move, ParseError> {
-//a, b is current players position
-//m, n is other players position
+pub fn move_from_str(s: &str, board: &Board, notation: Notation) -> Result<Move, ParseError> {
+//parses a move of the player to move on board
 	match notation {
 		Relative => {
-			match s {
-				"N"  => return Ok(Step(1) ),
-				"E"  => return Ok(Step(2) ),
-				"S"  => return Ok(Step(3) ),
-				"W"  => return Ok(Step(4) ),
-				"NN" => return Ok(Step(11)),
-				"NE" => return Ok(Step(12)),
-				"NW" => return Ok(Step(14)),
-				"EN" => return Ok(Step(21)),
-				"EE" => return Ok(Step(22)),
-				"ES" => return Ok(Step(23)),
-				"WN" => return Ok(Step(41)),
-				"WS" => return Ok(Step(43)),
-				"WW" => return Ok(Step(44)),
-				"SS" => return Ok(Step(33)),
-				"SE" => return Ok(Step(32)),
-				"SW" => return Ok(Step(34)),
+			//a pawn move is one direction for a step, two for a jump, the second one turned for a diagonal jump
+			let dirs: Option<Vec<Direction>> = s.chars().map(_direction_from_char).collect();
+			match dirs.as_deref() {
+				Some([d]) => return Ok(Step(*d)),
+				Some([d, e]) if d == e => return Ok(Jump(*d)),
+				Some([d, e]) => return d.turn_to(*e).map(|t| DiagonalJump(*d, t)).ok_or(InvalidMove),
 				_ => (),
 			}
 
-			let (i, x, y);
-			match s.chars().nth(0) {
-				Some(c) => match c {
-					'H' => i = 0,
-					'V' => i = 1,
-					_ => return Err(InvalidMove),
-				},
-				None => return Err(InvalidMove),
-			};
-			match s.chars().nth(1) {
-				Some(c) => match c {
-					'a' => x = 0,
-					'b' => x = 1,
-					'c' => x = 2,
-					'd' => x = 3,
-					'e' => x = 4,
-					'f' => x = 5,
-					'g' => x = 6,
-					'h' => x = 7,
-					_ => return Err(InvalidMove),
-				},
-				None => return Err(InvalidMove),
+			//a wall is 'H' or 'V' followed by the choordinates of its centre
+			let mut chars = s.chars();
+			let orientation = match chars.next() {
+				Some('H') => Horizontal,
+				Some('V') => Vertical,
+				_ => return Err(InvalidMove),
 			};
-			match s.chars().nth(2) {
-				Some(c) => match c {
-					'1' => y = 0,
-					'2' => y = 1,
-					'3' => y = 2,
-					'4' => y = 3,
-					'5' => y = 4,
-					'6' => y = 5,
-					'7' => y = 6,
-					'8' => y = 7,
-					_ => return Err(InvalidMove),
-				},
-				None => return Err(InvalidMove),
-			};
-			Ok(Move::Wall([i, x, y]))
+			match square_from_str(chars.as_str()) {
+				Some([x, y]) => Ok(Wall { orientation, x, y }),
+				None => Err(InvalidMove),
+			}
 		},
 		Absolute => {
-			let (x, y);
-			match s.chars().nth(0) {
-				Some(c) => match c {
-					'a' => x = 0,
-					'b' => x = 1,
-					'c' => x = 2,
-					'd' => x = 3,
-					'e' => x = 4,
-					'f' => x = 5,
-					'g' => x = 6,
-					'h' => x = 7,
-					'i' => x = 8,
-					_ => return Err(InvalidMove),
-				},
-				None => return Err(InvalidMove),
-			};
-			match s.chars().nth(1) {
-				Some(c) => match c {
-					'1' => y = 0,
-					'2' => y = 1,
-					'3' => y = 2,
-					'4' => y = 3,
-					'5' => y = 4,
-					'6' => y = 5,
-					'7' => y = 6,
-					'8' => y = 7,
-					'9' => y = 8,
-					_ => return Err(InvalidMove),
-				},
-				None => return Err(InvalidMove),
-			};
-			match (x-(a as isize), y-(b as isize)) {
-				(0,1)  => return Ok(Step(1) ),
-				(1,0)  => return Ok(Step(2) ),
-				(0,-1) => return Ok(Step(3) ),
-				(-1,0) => return Ok(Step(4) ),
-				(0,2)  => return Ok(Step(11)),
-				(2,0)  => return Ok(Step(22)),
-				(0,-2) => return Ok(Step(33)),
-				(-2,0) => return Ok(Step(44)),
-				(1,1) => {
-					match ((m as isize)-(a as isize), (n as isize)-(b as isize)) {
-						(1,0) => return Ok(Step(12)),
-						_ => return Ok(Step(21)),
-					}
-				},
-				(1,-1) => {
-					match ((m as isize)-(a as isize), (n as isize)-(b as isize)) {
-						(1,0) => return Ok(Step(14)),
-						_ => return Ok(Step(41)),
-					}
-				},
-				(-1,1) => {
-					match ((m as isize)-(a as isize), (n as isize)-(b as isize)) {
-						(-1,0) => return Ok(Step(41)),
-						_ => return Ok(Step(14)),
-					}
-				},
-				(-1,-1) => {
-					match ((m as isize)-(a as isize), (n as isize)-(b as isize)) {
-						(-1,0) => return Ok(Step(42)),
-						_ => return Ok(Step(24)),
-					}
-				},
-				_ => (),
-			};
-			let (i, x, y);
-			match s.chars().nth(3) {
-				Some(c) => match c {
-					'h' => i = 0,
-					'v' => i = 1,
-					_ => return Err(InvalidMove),
+			//a square alone is a step, a square followed by an orientation is a wall
+			let (square, orientation) = match s.strip_suffix('h') {
+				Some(square) => (square, Some(Horizontal)),
+				None => match s.strip_suffix('v') {
+					Some(square) => (square, Some(Vertical)),
+					None => (s, None),
 				},
-				None => return Err(InvalidMove),
 			};
-			match s.chars().nth(1) {
-				Some(c) => match c {
-					'a' => x = 0,
-					'b' => x = 1,
-					'c' => x = 2,
-					'd' => x = 3,
-					'e' => x = 4,
-					'f' => x = 5,
-					'g' => x = 6,
-					'h' => x = 7,
-					_ => return Err(InvalidMove),
-				},
+			let [x, y] = match square_from_str(square) {
+				Some([x, y]) => [x as isize, y as isize],
 				None => return Err(InvalidMove),
 			};
-			match s.chars().nth(2) {
-				Some(c) => match c {
-					'1' => y = 0,
-					'2' => y = 1,
-					'3' => y = 2,
-					'4' => y = 3,
-					'5' => y = 4,
-					'6' => y = 5,
-					'7' => y = 6,
-					'8' => y = 7,
-					_ => return Err(InvalidMove),
+			if let Some(orientation) = orientation {
+				return Ok(Wall { orientation, x: x as usize, y: y as usize });
+			}
+			let [a, b] = board.players[board.to_move_indices().0];
+			let (dx, dy) = (x-(a as isize), y-(b as isize));
+			let horizontal = if dx > 0 { East } else { West };
+			let vertical = if dy > 0 { North } else { South };
+			match (dx.abs(), dy.abs()) {
+				(1, 0) => Ok(Step(horizontal)),
+				(0, 1) => Ok(Step(vertical)),
+				(2, 0) => Ok(Jump(horizontal)),
+				(0, 2) => Ok(Jump(vertical)),
+				//a diagonal square can be reached over the pawn beside or over the pawn in front of the player.
+				//the legal one is taken, if neither is legal the one over a pawn, to report why it is illegal
+				(1, 1) => {
+					let jumps = [
+						(DiagonalJump(horizontal, horizontal.turn_to(vertical).unwrap()), [x as usize, b]),
+						(DiagonalJump(vertical, vertical.turn_to(horizontal).unwrap()), [a, y as usize])];
+					let jump = jumps.iter().find(|(m, _)| board.check_pawn_move(m).is_ok())
+						.or(jumps.iter().find(|(_, over)| board.pawns().contains(over)))
+						.unwrap_or(&jumps[0]);
+					Ok(jump.0)
 				},
-				None => return Err(InvalidMove),
-			};
-			Ok(Move::Wall([i, x, y]))
+				_ => Err(InvalidMove),
+			}
 		}
 	}
 }
 
-pub fn string_from_move(mv: &Move, [a, b]: [usize; 2], notation: Notation) -> String {
-	//a, b is other players position
-	match notation {
-		Relative => {
-			match mv {
-				Step(1)   	=> return format!("N") ,
-				Step(2) 	=> return format!("E") ,
-				Step(3) 	=> return format!("S") ,
-				Step(4) 	=> return format!("W") ,
-				Step(11)	=> return format!("NN"), 
-				Step(12)	=> return format!("NE"),
-				Step(14)	=> return format!("NW"),
-				Step(21)	=> return format!("EN"),
-				Step(22)	=> return format!("EE"),
-				Step(23)	=> return format!("ES"),
-				Step(41)	=> return format!("WN"),
-				Step(43)	=> return format!("WS"),
-				Step(44)	=> return format!("WW"),
-				Step(33)	=> return format!("SS"),
-				Step(32)	=> return format!("SE"),
-				Step(34)	=> return format!("SW"),
-				Step(_)   	=> return format!(" "), //cant happen
-				Wall([i, x, y]) => return format!("{}{}{}", _char_from_i(i, Relative), _char_from_x(x), y+1),
-			}
-		},
-		Absolute => {
-			match mv {
-				Wall([i, x, y]) => return format!("{}{}{}", _char_from_x(x), y+1, _char_from_i(i, Absolute)),
-				Step(1)   	=> return format!("{}{}", _char_from_x(&a), &b+2),
-				Step(2) 	=> return format!("{}{}", _char_from_x(&(a+1)), &(b+1)),
-				Step(3) 	=> return format!("{}{}", _char_from_x(&a), &b),
-				Step(4) 	=> return format!("{}{}", _char_from_x(&(a-1)), &(b+1)),
-				Step(11)	=> return format!("{}{}", _char_from_x(&a), &(b+3)),
-				Step(12)	=> return format!("{}{}", _char_from_x(&(a+1)), &(b+2)),
-				Step(14)	=> return format!("{}{}", _char_from_x(&(a-1)), &(b+2)),
-				Step(21)	=> return format!("{}{}", _char_from_x(&(a+1)), &(b+2)),
-				Step(22)	=> return format!("{}{}", _char_from_x(&(a+2)), &(b+1)),
-				Step(23)	=> return format!("{}{}", _char_from_x(&(a+1)), b),
-				Step(41)	=> return format!("{}{}", _char_from_x(&(a-1)), &(b+2)),
-				Step(43)	=> return format!("{}{}", _char_from_x(&(a-1)), &b),
-				Step(44)	=> return format!("{}{}", _char_from_x(&(a-2)), &(b+1)),
-				Step(33)	=> return format!("{}{}", _char_from_x(&a), &(b-1)),
-				Step(32)	=> return format!("{}{}", _char_from_x(&(a+1)), &b),
-				Step(34)	=> return format!("{}{}", _char_from_x(&(a-1)), &b),
-				Step(_)   	=> return format!(" "), //cant happen
-			}
-		}
+pub fn string_from_move(mv: &Move, board: &Board, notation: Notation) -> String {
+	//writes a move of the player to move on board
+	match (mv, notation) {
+		(Wall { orientation, x, y }, Relative) => format!("{}{}{}", _char_from_orientation(*orientation, Relative), _char_from_x(x), y+1),
+		(Wall { orientation, x, y }, Absolute) => format!("{}{}{}", _char_from_x(x), y+1, _char_from_orientation(*orientation, Absolute)),
+		(Step(d), Relative) => format!("{}", _char_from_direction(*d)),
+		(Jump(d), Relative) => format!("{}{}", _char_from_direction(*d), _char_from_direction(*d)),
+		(DiagonalJump(d, t), Relative) => format!("{}{}", _char_from_direction(*d), _char_from_direction(d.turn(*t))),
+		(_, Absolute) => string_from_square(Board::landing(board.players[board.to_move_indices().0], mv)),
 	}
 }
 
-fn _char_from_i(i: &usize, notation: Notation) -> char {
-	match notation {
-		Relative => {
-			match i {
-				0 => return 'H',
-				1 => return 'V',
-				_ => return ' ', //cant happen
-			}
-		},
-		Absolute => {
-			match i {
-				0 => return 'h',
-				1 => return 'v',
-				_ => return ' ', //cant happen
-			}
-		}
+pub fn string_from_square([x, y]: [usize; 2]) -> String {
+	format!("{}{}", _char_from_x(&x), y+1)
+}
+
+pub fn square_from_str(s: &str) -> Option<[usize; 2]> {
+	//parses a square in absolute notation like 'e1'
+	let mut chars = s.chars();
+	let x = "abcdefghijk".find(chars.next()?)?;
+	let y = chars.as_str().parse::<usize>().ok()?;
+	if !(1..=11).contains(&y) { return None };
+	Some([x, y-1])
+}
+
+fn _char_from_orientation(orientation: Orientation, notation: Notation) -> char {
+	match (orientation, notation) {
+		(Horizontal, Relative) => 'H',
+		(Vertical, Relative) => 'V',
+		(Horizontal, Absolute) => 'h',
+		(Vertical, Absolute) => 'v',
+	}
+}
+
+fn _char_from_direction(d: Direction) -> char {
+	match d {
+		North => 'N',
+		East => 'E',
+		South => 'S',
+		West => 'W',
+	}
+}
+
+fn _direction_from_char(c: char) -> Option<Direction> {
+	match c {
+		'N' => Some(North),
+		'E' => Some(East),
+		'S' => Some(South),
+		'W' => Some(West),
+		_ => None,
 	}
-		
 }
 
 fn _char_from_x(x: &usize) -> char {
@@ -263,6 +151,8 @@ fn _char_from_x(x: &usize) -> char {
 		6 => return 'g',
 		7 => return 'h',
 		8 => return 'i',
+		9 => return 'j',
+		10 => return 'k',
 		_ => return ' ', //cant happen
 	}
 }
@@ -278,6 +168,23 @@ pub fn help(s: &Option<String>, help_texts: &HashMap<HelpMessage, String>) {
             "show" => println!("    {}", help_texts.get(&HelpMessage::Show).unwrap()),
             "notation" => println!("    {}", help_texts.get(&HelpMessage::Notation).unwrap()),
             "brute" => println!("    {}", help_texts.get(&HelpMessage::Brute).unwrap()),
+            "think" => println!("    {}", help_texts.get(&HelpMessage::Think).unwrap()),
+            "bench" => println!("    {}", help_texts.get(&HelpMessage::Bench).unwrap()),
+            "perft" => println!("    {}", help_texts.get(&HelpMessage::Perft).unwrap()),
+            "eval" => println!("    {}", help_texts.get(&HelpMessage::Eval).unwrap()),
+            "weights" => println!("    {}", help_texts.get(&HelpMessage::Weights).unwrap()),
+            "tune" => println!("    {}", help_texts.get(&HelpMessage::Tune).unwrap()),
+            "mcts" => println!("    {}", help_texts.get(&HelpMessage::Mcts).unwrap()),
+            "engine" => println!("    {}", help_texts.get(&HelpMessage::Engine).unwrap()),
+            "match" => println!("    {}", help_texts.get(&HelpMessage::Match).unwrap()),
+            "sprt" => println!("    {}", help_texts.get(&HelpMessage::Sprt).unwrap()),
+            "play" => println!("    {}", help_texts.get(&HelpMessage::Play).unwrap()),
+            "save" => println!("    {}", help_texts.get(&HelpMessage::Save).unwrap()),
+            "load" => println!("    {}", help_texts.get(&HelpMessage::Load).unwrap()),
+            "export" => println!("    {}", help_texts.get(&HelpMessage::Export).unwrap()),
+            "import" => println!("    {}", help_texts.get(&HelpMessage::Import).unwrap()),
+            "position" => println!("    {}", help_texts.get(&HelpMessage::Position).unwrap()),
+            "legal" => println!("    {}", help_texts.get(&HelpMessage::Legal).unwrap()),
             "set" => println!("    {}", help_texts.get(&HelpMessage::Set).unwrap()),
             "unset" => println!("    {}", help_texts.get(&HelpMessage::Unset).unwrap()),
             "undo" => println!("    {}", help_texts.get(&HelpMessage::Undo).unwrap()),
@@ -301,8 +208,7 @@ pub fn get_and_parse_input() -> Result<IoState, ParseError> {
     match (new.get(0), new.get(1)) {
         (Some(s), None) => match s.as_str() {
             "help" | "h" => return Ok(Help(None)),
-            "new" | "n" => return Ok(NewBoard),
-            "new5x5" => return Ok(NewBoard5x5),
+            "new" | "n" => return Ok(NewBoard(9, 2)),
             "move" | "m" => return Err(NoMovesGiven),
             "_move_no_check" | "_mnc" => return Err(NoMovesGiven),
             "show" | "s" => return Ok(ShowBoard),
@@ -311,15 +217,27 @@ pub fn get_and_parse_input() -> Result<IoState, ParseError> {
             "unset" => return Err(NoFlagGiven),
             "notation" => return Err(NoNotationGiven),
             "brute" | "b" => return Err(BruteNoDepthGiven),
-            "brute5x5" => return Err(BruteNoDepthGiven),
+            "think" | "t" => return Err(ThinkNoTimeGiven),
+            "bench" => return Ok(Bench(3)),
+            "perft" => return Err(PerftNoDepthGiven),
+            "eval" => return Ok(Eval),
+            "weights" => return Ok(ShowWeights),
+            "tune" => return Err(TuneNoGamesGiven),
+            "mcts" => return Err(MctsNoIterationsGiven),
+            "engine" => return Ok(ShowEngine),
+            "match" => return Err(MatchArgumentsIncorrect),
+            "sprt" => return Err(SprtArgumentsIncorrect),
+            "play" | "p" => return Err(NoSideGiven),
+            "position" => return Ok(ShowPosition),
+            "legal" => return Ok(Legal(LegalFilter::All)),
+            "save" | "load" | "export" | "import" => return Err(NoFileGiven),
             "fill" => return Err(NoFillChordsGiven),
             "undo" => return Ok(Undo(1)),
             _ => return Err(UnknownCommand),
             },
         (Some(s), Some(i)) => match s.as_str() {
                 "help" | "h" => return Ok(Help(Some((*i.clone()).to_string()))),
-                "new" | "n" => return Err(InputAfterNew),
-                "new5x5" => return Err(InputAfterNew),
+                "new" | "n" => return _new_from_str(i.clone()),
                 "move" | "m" => return Ok(PlayMoves(i.clone())),
                 "_move_no_check" | "_mnc" => return Ok(PlayMovesNoCheck(i.clone())),
                 "show" | "s" => return Err(InputAfterShow),
@@ -328,7 +246,25 @@ pub fn get_and_parse_input() -> Result<IoState, ParseError> {
                 "unset" => return Ok(Unset(_flag_from_str(i.clone())?)),
                 "notation" => return Ok(SetNotation(_notation_from_str(i.clone())?)),
                 "brute" | "b" => return Ok(Brute(_int_from_str(i.clone())?)),
-                "brute5x5" => return Ok(Brute5x5(_int_from_str(i.clone())?)),
+                "think" | "t" => return Ok(Think(_int_from_str(i.clone())?)),
+                "bench" => return Ok(Bench(_int_from_str(i.clone())?)),
+                "perft" => return Ok(Perft(_int_from_str(i.clone())?)),
+                "weights" => return _weights_from_str(i),
+                "tune" => return _tune_from_str(i),
+                "mcts" => return _mcts_from_str(i),
+                "engine" => return Ok(SetEngine(_engine_from_str(i)?)),
+                "match" => return Ok(Match(_match_from_str(i, false)?)),
+                "sprt" => return Ok(Match(_match_from_str(i, true)?)),
+                "play" | "p" => return _play_from_str(i.clone()),
+                "save" => { let (path, tag) = _file_and_tag_from_str(i); return Ok(Save(path, tag)) },
+                "load" => { let (path, tag) = _file_and_tag_from_str(i); return Ok(Load(path, tag)) },
+                "export" => {
+                    let mut args = i.split_whitespace().map(|a| a.to_string());
+                    return Ok(Export(args.next().unwrap_or_default(), args.collect()));
+                },
+                "import" => return Ok(Import(i.trim().to_string())),
+                "position" => return Ok(SetPosition(i.clone())),
+                "legal" => return Ok(Legal(_legal_filter_from_str(i)?)),
                 "fill" => return Ok(Fill(i.clone())),
                 "undo" => return Ok(Undo(_int_from_str(i.clone())?)),
                 _ => return Err(UnknownCommand),
@@ -352,9 +288,186 @@ fn _notation_from_str(s: String) -> Result<Notation, ParseError> {
 	}
 }
 
+fn _engine_from_str(s: &str) -> Result<EngineKind, ParseError> {
+	match s.trim() {
+		"alphabeta" | "brute" => Ok(EngineKind::AlphaBeta),
+		"mcts" => Ok(EngineKind::Mcts),
+		"random" => Ok(EngineKind::Random),
+		"greedy" => Ok(EngineKind::Greedy),
+		"walls" => Ok(EngineKind::WallSpammer),
+		_ => Err(UnknownEngine),
+	}
+}
+
+fn _match_from_str(s: &str, sprt: bool) -> Result<MatchSettings, ParseError> {
+	//'<engine> <engine> <games>' followed by options like 'depth 3', 'time 0.5', 'moves 150' or 'openings <file>'.
+	//an engine can be followed by a weights file, like 'alphabeta:tuned.cfg'.
+	//an sprt also takes 'elo0', 'elo1', 'alpha' and 'beta', and the games are the most it plays
+	let incorrect = if sprt { SprtArgumentsIncorrect } else { MatchArgumentsIncorrect };
+	let args: Vec<&str> = s.split_whitespace().collect();
+	let [first, second, games, options @ ..] = &args[..] else { return Err(incorrect) };
+	let contestant = |s: &str| -> Result<(EngineKind, Option<String>), ParseError> {
+		match s.split_once(':') {
+			Some((engine, weights)) => Ok((_engine_from_str(engine)?, Some(weights.to_string()))),
+			None => Ok((_engine_from_str(s)?, None)),
+		}
+	};
+	let mut settings = MatchSettings {
+		engines: [contestant(first)?, contestant(second)?],
+		games: _int_from_str(games.to_string())?,
+		depth: None,
+		time: None,
+		max_moves: 200,
+		openings: None,
+		sprt: if sprt { Some(SprtSettings { elo0: 0.0, elo1: 10.0, alpha: 0.05, beta: 0.05 }) } else { None },
+	};
+	for pair in options.chunks(2) {
+		match (pair, settings.sprt.as_mut()) {
+			(["depth", d], _) => settings.depth = Some(_int_from_str(d.to_string())?),
+			(["time", t], _) => settings.time = Some(Duration::try_from_secs_f64(_float_from_str(t)?).map_err(|_| NotANumber)?),
+			(["moves", m], _) => settings.max_moves = _int_from_str(m.to_string())?,
+			(["openings", path], _) => settings.openings = Some(path.to_string()),
+			(["elo0", e], Some(sprt)) => sprt.elo0 = _float_from_str(e)?,
+			(["elo1", e], Some(sprt)) => sprt.elo1 = _float_from_str(e)?,
+			(["alpha", a], Some(sprt)) => sprt.alpha = _float_from_str(a)?,
+			(["beta", b], Some(sprt)) => sprt.beta = _float_from_str(b)?,
+			_ => return Err(incorrect),
+		}
+	}
+	if let Some(sprt) = settings.sprt {
+		let chance = |p: f64| p > 0.0 && p < 1.0;
+		if sprt.elo1 <= sprt.elo0 || !chance(sprt.alpha) || !chance(sprt.beta) {
+			return Err(SprtArgumentsIncorrect);
+		}
+	}
+	if settings.depth.is_none() && settings.time.is_none() { settings.depth = Some(2) };
+	Ok(settings)
+}
+
+fn _float_from_str(s: &str) -> Result<f64, ParseError> {
+	s.parse::<f64>().ok().filter(|x| x.is_finite()).ok_or(NotANumber)
+}
+
+fn _legal_filter_from_str(s: &str) -> Result<LegalFilter, ParseError> {
+	//'steps', 'walls' or 'near <square>'
+	match s.split_whitespace().collect::<Vec<_>>()[..] {
+		["steps"] => Ok(LegalFilter::Steps),
+		["walls"] => Ok(LegalFilter::Walls),
+		["near", square] => square_from_str(square).map(LegalFilter::Near).ok_or(UnknownLegalFilter),
+		_ => Err(UnknownLegalFilter),
+	}
+}
+
+fn _weights_from_str(s: &str) -> Result<IoState, ParseError> {
+	//'load <file>', 'save <file>' or '<weight> <value>'
+	match s.trim().split_once(' ') {
+		Some(("load", path)) => Ok(LoadWeights(path.trim().to_string())),
+		Some(("save", path)) => Ok(SaveWeights(path.trim().to_string())),
+		Some((name, value)) if Weights::NAMES.contains(&name) => match value.trim().parse::<isize>() {
+			Ok(value) => Ok(SetWeight(name.to_string(), value)),
+			Err(_) => Err(NotANumber),
+		},
+		_ => Err(UnknownWeight),
+	}
+}
+
+fn _tune_from_str(s: &str) -> Result<IoState, ParseError> {
+	//'<games> [depth] [file]', the games are searched to depth 2 if no depth is given
+	//and the weights are written to TUNED_FILE if no file is given
+	let mut args = s.split_whitespace().peekable();
+	let games = _int_from_str(args.next().ok_or(TuneNoGamesGiven)?.to_string())?;
+	let depth = match args.next_if(|a| a.parse::<usize>().is_ok()) {
+		Some(depth) => _int_from_str(depth.to_string())?,
+		None => 2,
+	};
+	let path = args.collect::<Vec<&str>>().join(" ");
+	Ok(Tune(games, depth, if path.is_empty() { TUNED_FILE.to_string() } else { path }))
+}
+
+fn _mcts_from_str(s: &str) -> Result<IoState, ParseError> {
+	//'<iterations>' or 'time <seconds>'
+	match s.split_whitespace().collect::<Vec<_>>()[..] {
+		["time", seconds] => Ok(MctsTime(_int_from_str(seconds.to_string())?)),
+		[iterations] => Ok(Mcts(_int_from_str(iterations.to_string())?)),
+		_ => Err(MctsNoIterationsGiven),
+	}
+}
+
+fn _play_from_str(s: String) -> Result<IoState, ParseError> {
+	//'play off', 'play <side>' or 'play <side> <seconds>'. The engine gets the other side
+	let args: Vec<&str> = s.split_whitespace().collect();
+	let seconds = match args.get(1) {
+		Some(t) => _int_from_str(t.to_string())?,
+		None => 3,
+	};
+	match args.first() {
+		Some(&"off") => Ok(EndPlay),
+		Some(&"1") => Ok(Play(Player2, seconds)),
+		Some(&"2") => Ok(Play(Player1, seconds)),
+		_ => Err(UnknownSide),
+	}
+}
+
+fn _new_from_str(s: String) -> Result<IoState, ParseError> {
+	//'new <size>' or 'new <size> <players>'
+	let args: Vec<&str> = s.split_whitespace().collect();
+	let size = match args.first().map(|a| a.parse::<usize>()) {
+		Some(Ok(size)) if Board::is_valid_size(size) => size,
+		_ => return Err(InvalidBoardSize),
+	};
+	match args.get(1).map(|a| a.parse::<usize>()) {
+		None => Ok(NewBoard(size, 2)),
+		Some(Ok(players)) if args.len() == 2 && (players == 2 || players == 4) => Ok(NewBoard(size, players)),
+		_ => Err(InvalidPlayerCount),
+	}
+}
+
+fn _file_and_tag_from_str(s: &str) -> (String, Option<String>) {
+	//'<file>' or '<file> <tag>', the tag may contain spaces
+	match s.trim().split_once(' ') {
+		Some((path, tag)) => (path.to_string(), Some(tag.trim().to_string())),
+		None => (s.trim().to_string(), None),
+	}
+}
+
 fn _int_from_str(s: String) -> Result<usize, ParseError> {
 	match s.parse::<usize>() {
 		Ok(n) => Ok(n),
 		Err(_) => Err(NotANumber),
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bots::mostly_pawn_move;
+
+	#[test]
+	fn every_legal_move_round_trips() {
+		//plays random games on all sizes with two and four players, preferring pawn moves so pawns meet and jump diagonally.
+		//every legal move of every position has to be written and parsed back to a legal move with the same result
+		let mut state = 0x2545f4914f6cdd1d;
+		let mut diagonal_jumps = 0;
+		for game in 0..150 {
+			let mut b = Board::with_players(3 + 2 * (game % 5), [2, 4][game / 5 % 2]);
+			while !b.is_over() && b.move_sequence.len() < 60 {
+				let moves = b.all_legal_moves();
+				for m in &moves {
+					if matches!(m, DiagonalJump(..)) { diagonal_jumps += 1 };
+					let mut after = b.clone();
+					after.mv(m);
+					for notation in [Relative, Absolute] {
+						let s = string_from_move(m, &b, notation);
+						let parsed = move_from_str(&s, &b, notation).unwrap_or_else(|_| panic!("{} does not parse", s));
+						assert_eq!(b.clone().check_move(&parsed), Ok(()), "{} in {}", s, b.to_position_string());
+						let mut replayed = b.clone();
+						replayed.mv(&parsed);
+						assert_eq!(replayed.hash, after.hash, "{} in {}", s, b.to_position_string());
+					}
+				}
+				let Some(m) = mostly_pawn_move(&moves, &mut state) else { break };
+				b.mv(&m);
+			}
+		}
+		assert!(diagonal_jumps > 100);
+	}
+}
@@ -2,9 +2,10 @@ use std::io;
 use std::io::Write;
 use std::collections::HashMap;
 use std::fs;
+use std::time::{ Instant, Duration };
 
 mod vis;
-use crate::vis::print_board;
+use crate::vis::{ print_board, print_legal_moves };
 
 mod helpers;
 use crate::helpers::move_from_str;
@@ -19,11 +20,47 @@ use crate::enums::{
     Notation, Notation::*,
     ParseError, ParseError::*,
     MoveError, MoveError::*,
+    LoadSaveError, LoadSaveError::*,
     Flag, Flag::*,
+    Player,
+    EngineKind,
+    MatchSettings,
     HelpMessage };
 
 mod brute;
-use crate::brute::{ brute_force, brute_force_5x5 };
+use crate::brute::{ brute_force, think, reply, bench };
+
+mod zobrist;
+
+mod save_load;
+use crate::save_load::{ save, load, DEFAULT_TAG };
+
+mod record;
+use crate::record::{ export, import };
+
+mod tt;
+use crate::tt::TranspositionTable;
+
+mod perft;
+use crate::perft::print_perft;
+
+mod eval;
+use crate::eval::{ Weights, WEIGHTS_FILE, print_eval, print_weights };
+
+mod tune;
+use crate::tune::tune;
+
+mod mcts;
+use crate::mcts::print_mcts;
+
+mod engine;
+use crate::engine::new_engine;
+
+mod bots;
+
+mod arena;
+use crate::arena::{ Contestant, load_openings, run_match, print_score };
+use crate::engine::SearchLimits;
 
 pub struct InteractiveInstance {
     pub board: Board,
@@ -34,7 +71,12 @@ pub struct InteractiveInstance {
     pub notation: Notation,
     pub parse_errors: HashMap<ParseError, String>,
     pub move_errors: HashMap<MoveError, String>,
+    pub load_save_errors: HashMap<LoadSaveError, String>,
     pub help_messages: HashMap<HelpMessage, String>,
+    pub tt: TranspositionTable,
+    pub weights: Weights,
+    pub engine: EngineKind,                  //used by brute, think and play
+    pub opponent: Option<(Player, usize)>,   //side the engine plays and its seconds per move, when playing against it
 }
 
 fn main() {
@@ -55,17 +97,11 @@ fn main() {
                 }
             },
             Help(s) => { help(&s, &instance.help_messages); instance.state = Await; },
-            NewBoard => {
+            NewBoard(size, players) => {
                 println!("    New board created!");
                 instance.history.push(instance.board.clone());
-                instance.board = Board::new();
-                instance.state = Await;
-                instance.input = None;
-            },
-            NewBoard5x5 => {
-                println!("    New board created!");
-                instance.history.push(instance.board.clone());
-                instance.board = Board::new5x5();
+                instance.board = Board::with_players(size, players);
+                instance.opponent = None;
                 instance.state = Await;
                 instance.input = None;
             },
@@ -73,7 +109,10 @@ fn main() {
                 let b = instance.board.clone();
                 match instance.board.extend(input.as_str(), &instance.move_errors, instance.notation.clone()) {
                     Ok(()) => {
-                        println!("    Board updated! Player {} to move.", instance.board.to_move_indices().0+1);
+                        match instance.board.goal_reached() {
+                            Some(p) => println!("    Board updated! Player {} reached the goal and wins, the game is over.", p+1),
+                            None => println!("    Board updated! Player {} to move.", instance.board.to_move_indices().0+1),
+                        }
                         instance.state = ShowBoard;
                         instance.history.push(b);
                     },
@@ -88,15 +127,115 @@ fn main() {
             ShowBoard => {
                 print_board(&instance);
                 instance.state = Await;
+                if let Some((engine, _)) = instance.opponent {
+                    if let Some(winner) = _winner(&instance.board) {
+                        println!("    Player {} wins! The game is over.", winner+1);
+                        instance.opponent = None;
+                    } else if instance.board.to_move == engine {
+                        instance.state = EngineMove;
+                    }
+                }
             },
             Set(flag) => { *instance.flags.get_mut(&flag).unwrap() = true; instance.state = Await; },
             SetNotation(not) => { instance.notation = not; instance.state = Await; },
             Unset(flag) => { *instance.flags.get_mut(&flag).unwrap() = false; instance.state = Await; },
+            Brute(_) | Think(_) | Play(_, _) | Eval | Mcts(_) | MctsTime(_) if instance.board.num_players != 2 => {
+                println!("    The engine only plays two player games.");
+                instance.state = Await;
+            },
             Brute(depth) => { 
-                brute_force(instance.board.clone(), depth, instance.notation.clone() ); instance.state = Await;
+                let mut engine = new_engine(instance.engine, &instance.tt, &instance.weights);
+                brute_force(engine.as_mut(), &instance.board, depth, instance.notation); instance.state = Await;
+            },
+            Think(seconds) => {
+                let mut engine = new_engine(instance.engine, &instance.tt, &instance.weights);
+                think(engine.as_mut(), &instance.board, seconds, instance.notation); instance.state = Await;
+            },
+            Bench(depth) => {
+                bench(depth); instance.state = Await;
+            },
+            Perft(depth) => {
+                print_perft(&instance.board, depth, instance.notation); instance.state = Await;
             },
-            Brute5x5(depth) => { 
-                brute_force_5x5(instance.board.clone(), depth, instance.notation.clone() ); instance.state = Await;
+            Eval => {
+                print_eval(&instance.board, &instance.weights); instance.state = Await;
+            },
+            ShowWeights => {
+                print_weights(&instance.weights); instance.state = Await;
+            },
+            SetWeight(name, value) => {
+                instance.weights.set(&name, value);
+                //scores stored with the old weights would mislead the search
+                instance.tt = TranspositionTable::new(20);
+                println!("    Weight {} set to {}", name, value);
+                instance.state = Await;
+            },
+            LoadWeights(path) => {
+                match Weights::load(&path) {
+                    Ok(weights) => {
+                        println!("    Weights loaded from {}", path);
+                        print_weights(&weights);
+                        instance.weights = weights;
+                        instance.tt = TranspositionTable::new(20);
+                    },
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.state = Await;
+            },
+            Mcts(iterations) => {
+                print_mcts(&instance.board, iterations, None, instance.notation); instance.state = Await;
+            },
+            MctsTime(seconds) => {
+                let deadline = Instant::now() + Duration::from_secs(seconds as u64);
+                print_mcts(&instance.board, usize::MAX, Some(deadline), instance.notation); instance.state = Await;
+            },
+            ShowEngine => {
+                println!("    The engine is {}", new_engine(instance.engine, &instance.tt, &instance.weights).name());
+                instance.state = Await;
+            },
+            SetEngine(kind) => {
+                println!("    Engine set to {}", new_engine(kind, &instance.tt, &instance.weights).name());
+                instance.engine = kind;
+                instance.state = Await;
+            },
+            Match(ref settings) => {
+                match _contestants(settings, &instance.tt, &instance.weights) {
+                    Ok(contestants) => {
+                        let openings = match &settings.openings {
+                            Some(path) => load_openings(path),
+                            None => Ok(vec![Board::new()]),
+                        };
+                        match openings {
+                            Ok(openings) => {
+                                let limits = SearchLimits { depth: settings.depth, time: settings.time };
+                                let contestants = [&contestants[0], &contestants[1]];
+                                let score = run_match(contestants, settings.games, &openings, limits, settings.max_moves, settings.sprt.as_ref());
+                                print_score(contestants, &score);
+                            },
+                            Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                        }
+                    },
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.state = Await;
+            },
+            Tune(games, depth, path) => {
+                let weights = tune(games, depth, &instance.weights);
+                print_weights(&weights);
+                match weights.save(&path) {
+                    Ok(()) => println!("    Tuned weights saved to {} and used from now on", path),
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.weights = weights;
+                instance.tt = TranspositionTable::new(20);
+                instance.state = Await;
+            },
+            SaveWeights(path) => {
+                match instance.weights.save(&path) {
+                    Ok(()) => println!("    Weights saved to {}", path),
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.state = Await;
             },
             Fill(input) => {
                 let b = instance.board.clone();
@@ -109,6 +248,98 @@ fn main() {
                     Err(err) => { println!("    {}", instance.parse_errors.get(&err).unwrap()); instance.state = Await },
                 }
             },
+            Play(engine, seconds) => {
+                let human = 2 - engine.index();
+                let name = new_engine(instance.engine, &instance.tt, &instance.weights).name();
+                println!("    Playing against the {} engine. You are player {}, the engine thinks {} seconds per move.", name, human, seconds);
+                instance.opponent = Some((engine, seconds));
+                instance.state = ShowBoard;
+            },
+            EndPlay => {
+                println!("    Stopped playing against the engine.");
+                instance.opponent = None;
+                instance.state = Await;
+            },
+            EngineMove => {
+                let (_, seconds) = instance.opponent.unwrap();
+                let mut engine = new_engine(instance.engine, &instance.tt, &instance.weights);
+                match reply(engine.as_mut(), &instance.board, seconds, instance.notation) {
+                    Some(m) => {
+                        instance.history.push(instance.board.clone());
+                        instance.board.mv(&m);
+                        instance.state = ShowBoard;
+                    },
+                    None => { println!("    The engine found no move."); instance.opponent = None; instance.state = Await },
+                }
+            },
+            Save(path, tag) => {
+                match save(&instance.board, &path, tag.as_deref().unwrap_or(DEFAULT_TAG)) {
+                    Ok(()) => println!("    Game saved to {}", path),
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.state = Await;
+            },
+            Load(path, tag) => {
+                match load(&path, tag.as_deref(), &instance.move_errors) {
+                    Ok(b) => {
+                        println!("    Game loaded from {}", path);
+                        instance.history.push(instance.board.clone());
+                        instance.board = b;
+                        instance.state = ShowBoard;
+                    },
+                    Err(IllegalMoveList(e)) => {
+                        println!("    {}", instance.load_save_errors.get(&IllegalMoveList(String::new())).unwrap());
+                        println!("    {e}");
+                        instance.state = Await;
+                    },
+                    Err(err) => { println!("    {}", instance.load_save_errors.get(&err).unwrap()); instance.state = Await },
+                }
+            },
+            Export(ref path, ref names) => {
+                match export(&instance.board, names, path, instance.notation) {
+                    Ok(()) => println!("    Game record written to {}", path),
+                    Err(err) => println!("    {}", instance.load_save_errors.get(&err).unwrap()),
+                }
+                instance.state = Await;
+            },
+            Import(path) => {
+                match import(&path) {
+                    Ok(record) => {
+                        println!("    Game record of {} from {} read from {}", record.names.join(" - "), record.date, path);
+                        instance.history.push(instance.board.clone());
+                        instance.board = record.board;
+                        instance.state = ShowBoard;
+                    },
+                    Err(InvalidRecordMove(m)) => {
+                        println!("    Move {} in the record is invalid encoding.", m);
+                        instance.state = Await;
+                    },
+                    Err(IllegalRecordMove(m, err)) => {
+                        println!("    Move {} in the record is illegal, because: {}.", m, instance.move_errors.get(&err).unwrap());
+                        instance.state = Await;
+                    },
+                    Err(err) => { println!("    {}", instance.load_save_errors.get(&err).unwrap()); instance.state = Await },
+                }
+            },
+            ShowPosition => {
+                println!("    {}", instance.board.to_position_string());
+                instance.state = Await;
+            },
+            Legal(filter) => {
+                print_legal_moves(&instance.board, filter, instance.notation);
+                instance.state = Await;
+            },
+            SetPosition(input) => {
+                match Board::from_position_string(&input) {
+                    Ok(b) => {
+                        println!("    Position set!");
+                        instance.history.push(instance.board.clone());
+                        instance.board = b;
+                        instance.state = ShowBoard;
+                    },
+                    Err(err) => { println!("    {}", instance.parse_errors.get(&err).unwrap()); instance.state = Await },
+                }
+            },
             Undo(i) => {
                 if instance.history.len() < i { println!("    {}", instance.parse_errors.get(&NotEnoughToUndo).unwrap()); }
                 else {
@@ -142,8 +373,22 @@ fn init() -> InteractiveInstance {
             "FillChordsIncorrect" => { parse_errors.insert(FillChordsIncorrect, error_pair[1].clone()); },
             "UnknownCommand" => { parse_errors.insert(UnknownCommand, error_pair[1].clone()); },
             "InputAfterShow" => { parse_errors.insert(InputAfterShow, error_pair[1].clone()); },
-            "InputAfterNew" => { parse_errors.insert(InputAfterNew, error_pair[1].clone()); },
+            "InvalidBoardSize" => { parse_errors.insert(InvalidBoardSize, error_pair[1].clone()); },
+            "InvalidPlayerCount" => { parse_errors.insert(InvalidPlayerCount, error_pair[1].clone()); },
+            "UnknownLegalFilter" => { parse_errors.insert(UnknownLegalFilter, error_pair[1].clone()); },
+            "UnknownWeight" => { parse_errors.insert(UnknownWeight, error_pair[1].clone()); },
+            "TuneNoGamesGiven" => { parse_errors.insert(TuneNoGamesGiven, error_pair[1].clone()); },
+            "MctsNoIterationsGiven" => { parse_errors.insert(MctsNoIterationsGiven, error_pair[1].clone()); },
+            "UnknownEngine" => { parse_errors.insert(UnknownEngine, error_pair[1].clone()); },
+            "MatchArgumentsIncorrect" => { parse_errors.insert(MatchArgumentsIncorrect, error_pair[1].clone()); },
+            "SprtArgumentsIncorrect" => { parse_errors.insert(SprtArgumentsIncorrect, error_pair[1].clone()); },
             "BruteNoDepthGiven" => { parse_errors.insert(BruteNoDepthGiven, error_pair[1].clone()); },
+            "ThinkNoTimeGiven" => { parse_errors.insert(ThinkNoTimeGiven, error_pair[1].clone()); },
+            "PerftNoDepthGiven" => { parse_errors.insert(PerftNoDepthGiven, error_pair[1].clone()); },
+            "NoSideGiven" => { parse_errors.insert(NoSideGiven, error_pair[1].clone()); },
+            "NoFileGiven" => { parse_errors.insert(NoFileGiven, error_pair[1].clone()); },
+            "PositionIncorrect" => { parse_errors.insert(PositionIncorrect, error_pair[1].clone()); },
+            "UnknownSide" => { parse_errors.insert(UnknownSide, error_pair[1].clone()); },
             "InvalidMove" => { parse_errors.insert(InvalidMove, error_pair[1].clone()); },
             "NotANumber" => { parse_errors.insert(NotANumber, error_pair[1].clone()); },
             "NotEnoughToUndo" => { parse_errors.insert(NotEnoughToUndo, error_pair[1].clone()); },
@@ -167,6 +412,31 @@ fn init() -> InteractiveInstance {
             "SpaceOccupied" => { move_errors.insert(SpaceOccupied, error_pair[1].clone()); },
             "P1NoPath" => { move_errors.insert(P1NoPath, error_pair[1].clone()); },
             "P2NoPath" => { move_errors.insert(P2NoPath, error_pair[1].clone()); },
+            "P3NoPath" => { move_errors.insert(P3NoPath, error_pair[1].clone()); },
+            "P4NoPath" => { move_errors.insert(P4NoPath, error_pair[1].clone()); },
+            "WallOutsideBoard" => { move_errors.insert(WallOutsideBoard, error_pair[1].clone()); },
+            "GameIsOver" => { move_errors.insert(GameIsOver, error_pair[1].clone()); },
+            _ => (),
+        }
+    }
+
+//create load_save_errors map
+    let mut load_save_errors = HashMap::new();
+    let load_save_errors_from_file = fs::read_to_string("Messages/LoadSaveErrors")
+        .expect("LoadSaveErrors file missing or corrupted.");
+    for error_pair_joined in load_save_errors_from_file.split("\n") {
+        let error_pair: Vec<String> = error_pair_joined.split(": ").map(|x| x.to_string()).collect();
+        match error_pair[0].as_str() {
+            "FileNotFound" => { load_save_errors.insert(FileNotFound, error_pair[1].clone()); },
+            "FileNotWritable" => { load_save_errors.insert(FileNotWritable, error_pair[1].clone()); },
+            "TagNotFound" => { load_save_errors.insert(TagNotFound, error_pair[1].clone()); },
+            "CorruptedGame" => { load_save_errors.insert(CorruptedGame, error_pair[1].clone()); },
+            "IllegalMoveList" => { load_save_errors.insert(IllegalMoveList(String::new()), error_pair[1].clone()); },
+            "UnsupportedBoardSize" => { load_save_errors.insert(UnsupportedBoardSize, error_pair[1].clone()); },
+            "CorruptedWeights" => { load_save_errors.insert(CorruptedWeights, error_pair[1].clone()); },
+            "CorruptedOpenings" => { load_save_errors.insert(CorruptedOpenings, error_pair[1].clone()); },
+            "TooManyNames" => { load_save_errors.insert(TooManyNames, error_pair[1].clone()); },
+            "WrongResult" => { load_save_errors.insert(WrongResult, error_pair[1].clone()); },
             _ => (),
         }
     }
@@ -185,6 +455,23 @@ fn init() -> InteractiveInstance {
             "Set" => { help_messages.insert(HelpMessage::Set, message_pair[1].clone()); },
             "Unset" => { help_messages.insert(HelpMessage::Unset, message_pair[1].clone()); },
             "Brute" => { help_messages.insert(HelpMessage::Brute, message_pair[1].clone()); },
+            "Think" => { help_messages.insert(HelpMessage::Think, message_pair[1].clone()); },
+            "Bench" => { help_messages.insert(HelpMessage::Bench, message_pair[1].clone()); },
+            "Perft" => { help_messages.insert(HelpMessage::Perft, message_pair[1].clone()); },
+            "Eval" => { help_messages.insert(HelpMessage::Eval, message_pair[1].clone()); },
+            "Weights" => { help_messages.insert(HelpMessage::Weights, message_pair[1].clone()); },
+            "Tune" => { help_messages.insert(HelpMessage::Tune, message_pair[1].clone()); },
+            "Mcts" => { help_messages.insert(HelpMessage::Mcts, message_pair[1].clone()); },
+            "Engine" => { help_messages.insert(HelpMessage::Engine, message_pair[1].clone()); },
+            "Match" => { help_messages.insert(HelpMessage::Match, message_pair[1].clone()); },
+            "Sprt" => { help_messages.insert(HelpMessage::Sprt, message_pair[1].clone()); },
+            "Play" => { help_messages.insert(HelpMessage::Play, message_pair[1].clone()); },
+            "Save" => { help_messages.insert(HelpMessage::Save, message_pair[1].clone()); },
+            "Load" => { help_messages.insert(HelpMessage::Load, message_pair[1].clone()); },
+            "Export" => { help_messages.insert(HelpMessage::Export, message_pair[1].clone()); },
+            "Import" => { help_messages.insert(HelpMessage::Import, message_pair[1].clone()); },
+            "Position" => { help_messages.insert(HelpMessage::Position, message_pair[1].clone()); },
+            "Legal" => { help_messages.insert(HelpMessage::Legal, message_pair[1].clone()); },
             "Notation" => { help_messages.insert(HelpMessage::Notation, message_pair[1].clone()); },
             "Fill" => { help_messages.insert(HelpMessage::Fill, message_pair[1].clone()); },
             "Undo" => { help_messages.insert(HelpMessage::Undo, message_pair[1].clone()); },
@@ -193,6 +480,16 @@ fn init() -> InteractiveInstance {
         }
     }
 
+//read the evaluation weights, the default weights are used without a weights file
+    let weights = match Weights::load(WEIGHTS_FILE) {
+        Ok(weights) => weights,
+        Err(FileNotFound) => Weights::default(),
+        Err(_) => {
+            println!("    The weights file {} is corrupted, the default weights are used.", WEIGHTS_FILE);
+            Weights::default()
+        },
+    };
+
 //create flags map
     let mut flags = HashMap::new();
     flags.insert(Invert, false);
@@ -206,7 +503,37 @@ fn init() -> InteractiveInstance {
         notation: Relative,
         parse_errors: parse_errors,
         move_errors: move_errors,
+        load_save_errors,
         help_messages: help_messages,
+        tt: TranspositionTable::new(20),
+        weights,
+        engine: EngineKind::AlphaBeta,
+        opponent: None,
+    }
+}
+
+fn _contestants(settings: &MatchSettings, tt: &TranspositionTable, weights: &Weights) -> Result<[Contestant; 2], LoadSaveError> {
+    //engines without a weights file of their own use the current weights
+    let contestant = |(kind, path): &(EngineKind, Option<String>)| -> Result<Contestant, LoadSaveError> {
+        let name = new_engine(*kind, tt, weights).name();
+        Ok(match path {
+            Some(path) => Contestant { kind: *kind, weights: Weights::load(path)?, label: format!("{} ({})", name, path) },
+            None => Contestant { kind: *kind, weights: *weights, label: name.to_string() },
+        })
+    };
+    let mut contestants = [contestant(&settings.engines[0])?, contestant(&settings.engines[1])?];
+    if contestants[0].label == contestants[1].label {
+        contestants[0].label.push_str(" 1");
+        contestants[1].label.push_str(" 2");
+    }
+    Ok(contestants)
+}
+
+fn _winner(board: &Board) -> Option<usize> {
+    //a game against the engine ends as soon as a pawn reached its goal or the player to move cannot be stopped anymore
+    match board.goal_reached() {
+        Some(p) => Some(p),
+        None => if board.current_player_wins() { Some(board.to_move_indices().0) } else { None },
     }
 }
 
@@ -0,0 +1,181 @@
+//contains a monte carlo tree search (uct) engine, an alternative to the alpha-beta search in brute.rs.
+//instead of a heuristic at a fixed depth it plays many quick games from the position, the playouts,
+//and spends more of them on the moves that won more of their playouts so far
+
+use crate::Board;
+use crate::enums::{ Notation, Move, Move::*, Orientation::* };
+use crate::helpers::string_from_move;
+use crate::bots::{ greedy_step, xorshift };
+use crate::tt::{ encode_move, decode_move };
+
+use std::time::{ Instant, SystemTime };
+
+use rayon::prelude::*;
+
+//weighs trying rarely visited moves against visiting the moves winning the most
+const EXPLORATION: f64 = 1.4;
+//the chance of a playout move being a random wall instead of a step along the shortest path
+const WALL_CHANCE: u64 = 10;            //in percent
+//playouts not decided after this many moves are decided by the distances to the goals
+const MAX_PLAYOUT_LENGTH: usize = 150;
+//every thread stops growing its tree at this many nodes, further playouts start at the leaves of the tree
+const MAX_NODES: usize = 1 << 18;
+
+struct Node {
+	mv: Option<Move>,              //the move leading here, None at the root
+	mover: usize,                  //the player who made the move
+	children: Vec<usize>,
+	untried: Option<Vec<u16>>,     //legal moves without a child yet, as codes of tt::encode_move. Generated on the first visit that expands
+	visits: u32,
+	wins: f64,                     //for the mover, a draw counts half
+}
+
+pub struct MoveStats {
+	pub mv: Move,
+	pub visits: u32,
+	pub win_rate: f64,             //for the player to move
+}
+
+pub fn mcts(board: &Board, iterations: usize, deadline: Option<Instant>) -> Vec<MoveStats> {
+	//root parallel: every thread grows its own tree, the visits and wins of the root moves are added up.
+	//returns the root moves, most visited first
+	let threads = rayon::current_num_threads().max(1);
+	let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(1);
+	let trees: Vec<Vec<(Move, u32, f64)>> = (0..threads)
+		.into_par_iter()
+		.map(|t| _search(board, iterations.div_ceil(threads), deadline, seed ^ (t as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15)))
+		.collect();
+
+	let mut stats: Vec<MoveStats> = vec![];
+	for (mv, visits, wins) in trees.into_iter().flatten() {
+		match stats.iter_mut().find(|s| s.mv == mv) {
+			Some(s) => { s.win_rate += wins; s.visits += visits },
+			None => stats.push(MoveStats { mv, visits, win_rate: wins }),
+		}
+	}
+	for s in stats.iter_mut() {
+		s.win_rate = if s.visits == 0 { 0.0 } else { s.win_rate / s.visits as f64 };
+	}
+	stats.sort_by_key(|s| std::cmp::Reverse(s.visits));
+	stats
+}
+
+pub fn print_mcts(board: &Board, iterations: usize, deadline: Option<Instant>, notation: Notation) {
+	//prints the ten most visited moves with their visits and win rates
+	let begin_time = Instant::now();
+	let stats = mcts(board, iterations, deadline);
+	if stats.is_empty() {
+		println!("    No legal moves.");
+		return;
+	}
+	let total: u32 = stats.iter().map(|s| s.visits).sum();
+	for s in stats.iter().take(10) {
+		println!("    {:<8}{:>9} visits    {:>5.1}% wins", string_from_move(&s.mv, board, notation), s.visits, 100.0 * s.win_rate);
+	}
+	println!("    Best move found:    {}    ({} playouts in {} ms)",
+		string_from_move(&stats[0].mv, board, notation), total, begin_time.elapsed().as_millis());
+}
+
+fn _search(board: &Board, iterations: usize, deadline: Option<Instant>, seed: u64) -> Vec<(Move, u32, f64)> {
+	//grows one tree. Moves are made and taken back on one board, like in brute.rs
+	let mut b = board.clone();
+	b.move_sequence.clear();
+	let mut rng = seed | 1;
+	let mut tree = vec![Node { mv: None, mover: 0, children: vec![], untried: None, visits: 0, wins: 0.0 }];
+	let mut path = vec![];
+
+	for _ in 0..iterations {
+		if deadline.is_some_and(|d| Instant::now() >= d) { break };
+		//selection, following the best uct value while every move of a node has a child
+		let mut node = 0;
+		path.clear();
+		path.push(0);
+		loop {
+			if b.is_over() { break };
+			let full = tree.len() >= MAX_NODES;
+			let n = &mut tree[node];
+			if n.untried.is_none() {
+				//a full tree is not expanded any further, the node stays a leaf
+				if full { break };
+				n.untried = Some(b.all_legal_moves().iter().map(|m| encode_move(&Some(*m)) as u16).collect());
+			}
+			if !n.untried.as_ref().unwrap().is_empty() || n.children.is_empty() { break };
+			let ln = (n.visits as f64).ln();
+			node = *tree[node].children.iter().max_by(|x, y| _uct(&tree[**x], ln).total_cmp(&_uct(&tree[**y], ln))).unwrap();
+			b.mv(&tree[node].mv.unwrap());
+			path.push(node);
+		}
+		//expansion, one untried move picked at random
+		if !b.is_over() && tree.len() < MAX_NODES {
+			let mover = b.to_move_indices().0;
+			let untried = tree[node].untried.as_mut().unwrap();
+			if !untried.is_empty() {
+				let m = decode_move(untried.swap_remove(xorshift(&mut rng) as usize % untried.len()) as u64).unwrap();
+				tree.push(Node { mv: Some(m), mover, children: vec![], untried: None, visits: 0, wins: 0.0 });
+				let child = tree.len() - 1;
+				tree[node].children.push(child);
+				b.mv(&m);
+				path.push(child);
+			}
+		}
+		//playout and backpropagation
+		let winner = _playout(&mut b, &mut rng);
+		for &n in path.iter().rev() {
+			let node = &mut tree[n];
+			node.visits += 1;
+			node.wins += match winner {
+				Some(w) if w == node.mover => 1.0,
+				Some(_) => 0.0,
+				None => 0.5,
+			};
+			if let Some(m) = node.mv { b.unmake(&m) };
+		}
+	}
+	tree[0].children.iter().map(|c| (tree[*c].mv.unwrap(), tree[*c].visits, tree[*c].wins)).collect()
+}
+
+fn _uct(node: &Node, ln_parent_visits: f64) -> f64 {
+	if node.visits == 0 { return f64::INFINITY };
+	let n = node.visits as f64;
+	node.wins / n + EXPLORATION * (ln_parent_visits / n).sqrt()
+}
+
+fn _playout(b: &mut Board, rng: &mut u64) -> Option<usize> {
+	//plays quick moves until a pawn reaches its goal, then takes them all back. Returns the winner, None for a draw.
+	//every move is a step along a shortest path, or sometimes a random wall
+	let mut moves = vec![];
+	while !b.is_over() && moves.len() < MAX_PLAYOUT_LENGTH {
+		let m = if xorshift(rng) % 100 < WALL_CHANCE && b.walls_left[b.to_move_indices().0] > 0 {
+			_random_wall(b, rng).or_else(|| greedy_step(b))
+		} else {
+			greedy_step(b)
+		};
+		let Some(m) = m else { break };
+		b.mv(&m);
+		moves.push(m);
+	}
+	let winner = match b.goal_reached() {
+		Some(w) => Some(w),
+		None => {
+			//undecided, the player closer to the goal wins. Equal distances are a draw
+			let (d0, d1) = (b.dist_to_goal(0), b.dist_to_goal(1));
+			if d0 < d1 { Some(0) } else if d1 < d0 { Some(1) } else { None }
+		},
+	};
+	for m in moves.iter().rev() {
+		b.unmake(m);
+	}
+	winner
+}
+
+fn _random_wall(b: &mut Board, rng: &mut u64) -> Option<Move> {
+	//a few random tries, walls are only a small part of the playout anyway
+	let n = b.size as u64 - 1;
+	for _ in 0..4 {
+		let r = xorshift(rng);
+		let orientation = if r & 1 == 0 { Horizontal } else { Vertical };
+		let m = Wall { orientation, x: (r >> 1 & 0xffff) as usize % n as usize, y: (r >> 17 & 0xffff) as usize % n as usize };
+		if b.check_move(&m).is_ok() { return Some(m) };
+	}
+	None
+}
@@ -0,0 +1,102 @@
+//contains perft, which counts the positions reached by all legal move sequences of a given length.
+//the counts only depend on the move rules, so comparing them with known counts finds mistakes in check_move and all_legal_moves
+
+use crate::Board;
+use crate::enums::{ Notation, Move };
+use crate::helpers::string_from_move;
+
+use std::time::Instant;
+
+use rayon::prelude::*;
+
+pub fn perft(board: &mut Board, depth: usize) -> u64 {
+	//the number of move sequences of length depth. Sequences ending early because the game is over are not counted
+	if depth == 0 { return 1 };
+	let moves = board.all_legal_moves();
+	if depth == 1 { return moves.len() as u64 };
+	let mut nodes = 0;
+	for m in &moves {
+		board.mv(m);
+		nodes += perft(board, depth-1);
+		board.unmake(m);
+	}
+	nodes
+}
+
+pub fn divide(board: &Board, depth: usize) -> Vec<(Move, u64)> {
+	//perft split up by the first move, every thread gets its own copy of the board
+	if depth == 0 { return vec![] };
+	board.clone().all_legal_moves()
+		.par_iter()
+		.map_init(|| board.clone(), |b, m| {
+			b.mv(m);
+			let nodes = perft(b, depth-1);
+			b.unmake(m);
+			(*m, nodes)
+		})
+		.collect()
+}
+
+pub fn print_perft(board: &Board, depth: usize, notation: Notation) {
+	//prints the count of every first move, then the total
+	let begin_time = Instant::now();
+	let counts = divide(board, depth);
+	for (m, nodes) in &counts {
+		println!("    {:<8}{}", string_from_move(m, board, notation), nodes);
+	}
+	let total: u64 = if depth == 0 { 1 } else { counts.iter().map(|(_, nodes)| nodes).sum() };
+	println!("    Perft {}: {} positions in {} ms", depth, total, begin_time.elapsed().as_millis());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn _perft(position: &str, depth: usize) -> u64 {
+		perft(&mut Board::from_position_string(position).unwrap(), depth)
+	}
+
+	//the counts of counted_by_hand are worked out by hand below each position. All other counts were
+	//counted by this engine, they only catch changes to the move generation
+
+	#[test]
+	fn counted_by_hand() {
+		//3 steps and all 128 walls, no single wall closes a path
+		assert_eq!(_perft("9 / e1 e9 / 10 10 / 1 /", 1), 131);
+		//after a step the 131 replies stay. A wall takes away itself, the crossing wall and the one or two overlapping walls,
+		//240 replies for each orientation. d8h and e8h block the step back, d8v and e8v a step aside:
+		//3 * 131 + 128 * 131 - 480 - 4
+		assert_eq!(_perft("9 / e1 e9 / 10 10 / 1 /", 2), 16677);
+		//steps to a1, c1 and b2 and the 8 walls, a wall covers only two of the three columns
+		assert_eq!(_perft("3 / b1 b3 / 1 1 / 1 /", 1), 11);
+		//3 steps and the jump over the pawn in front, and 128 walls
+		assert_eq!(_perft("9 / e5 e6 / 10 10 / 1 /", 1), 132);
+		//d6h blocks the jump, so the pawn jumps diagonally to d6 and f6 instead. d6h takes away itself,
+		//c6h, e6h and d6v, leaving 124 walls
+		assert_eq!(_perft("9 / e5 e6 / 10 10 / 1 / d6h", 1), 129);
+	}
+
+	#[test]
+	fn start_positions() {
+		assert_eq!(_perft("3 / b1 b3 / 1 1 / 1 /", 5), 3452);
+		assert_eq!(_perft("5 / c1 a3 c5 e3 / 2 2 2 2 / 1 /", 3), 31365);
+	}
+
+	#[test]
+	fn jumps() {
+		//straight over the pawn in front, diagonally past a wall behind it and diagonally at the edge of the board
+		assert_eq!(_perft("9 / e5 e6 / 10 10 / 1 /", 2), 16938);
+		assert_eq!(_perft("9 / e5 e6 / 10 10 / 1 / d6h", 2), 15922);
+		assert_eq!(_perft("9 / a8 a9 / 5 5 / 1 /", 2), 16412);
+		assert_eq!(_perft("9 / e4 e5 / 0 0 / 1 / d5h e4v", 6), 909);
+		//four players around one square, every pawn move can meet another pawn
+		assert_eq!(_perft("5 / c2 b3 c4 d3 / 1 1 1 1 / 1 / b2h c3v", 4), 173151);
+	}
+
+	#[test]
+	fn walls() {
+		//many walls, so many wall moves overlap others or would close the last path to a goal
+		assert_eq!(_perft("9 / c7 g2 / 3 2 / 1 / a2h c2h e2v f5h h5h a7h c7v d4h e4v f7h b5v g3v h2h e8h f3v", 3), 439833);
+		assert_eq!(_perft("7 / d3 d4 / 2 3 / 2 / c4h d3v b2v", 3), 203443);
+	}
+}
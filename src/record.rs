@@ -0,0 +1,250 @@
+// contains functionality for reading / writing game records, a pgn like format to exchange games.
+//
+// A record starts with header tags, followed by the numbered moves and the result, for example
+//
+//     [Player1 "Alice"]
+//     [Player2 "Bob"]
+//     [Date "2024.05.01"]
+//     [Result "*"]
+//     [Size "9"]
+//     [Players "2"]
+//     [Notation "absolute"]
+//
+//     1. e2 e8 2. e3 e7h *
+//
+// A four player game has a Player tag for every player, its moves are numbered in rounds of four
+// and its result names the winner like "0-0-1-0".
+//
+// A record of a game that did not start from the usual position also has a Position tag,
+// holding the start position as given by Board::to_position_string, like "9 / e1 e9 / 10 10 / 1 / d3h e6v".
+//
+// Unknown player names are written as "?". The Result tag has to match the moves, a record of a game
+// that is not over has the result "*".
+
+use std::fs;
+use std::time::SystemTime;
+
+use crate::Board;
+use crate::enums::{
+    Notation, Notation::*,
+    LoadSaveError, LoadSaveError::* };
+use crate::helpers::string_from_move;
+
+//a game with the metadata of its record
+pub struct Record {
+    pub board: Board,
+    pub names: Vec<String>,        //one per player, "?" if unknown
+    pub date: String,              //like "2024.05.01", "????.??.??" if unknown
+}
+
+pub fn export(board: &Board, names: &[String], path: &str, notation: Notation) -> Result<(), LoadSaveError> {
+    //players without a name given are written as "?"
+    if names.len() > board.num_players { return Err(TooManyNames) };
+    let names = (0..board.num_players).map(|p| names.get(p).cloned().unwrap_or("?".to_string())).collect();
+    let record = Record { board: board.clone(), names, date: _today() };
+    fs::write(path, write_record(&record, notation)).map_err(|_| FileNotWritable)
+}
+
+pub fn import(path: &str) -> Result<Record, LoadSaveError> {
+    let content = fs::read_to_string(path).map_err(|_| FileNotFound)?;
+    read_record(&content)
+}
+
+fn _result(board: &Board) -> String {
+    //the winner scores 1, everybody else 0, like '1-0' or '0-0-1-0'
+    match board.goal_reached() {
+        Some(w) => (0..board.num_players).map(|p| if p == w { "1" } else { "0" }).collect::<Vec<_>>().join("-"),
+        None => "*".to_string(),
+    }
+}
+
+pub fn write_record(record: &Record, notation: Notation) -> String {
+    let board = &record.board;
+    let start = board.start_position();
+    let result = _result(board);
+
+    let mut text = String::new();
+    for (p, name) in record.names.iter().enumerate() {
+        text.push_str(&format!("[Player{} \"{}\"]\n", p+1, name));
+    }
+    text.push_str(&format!("[Date \"{}\"]\n", record.date));
+    text.push_str(&format!("[Result \"{}\"]\n", result));
+    text.push_str(&format!("[Size \"{}\"]\n", board.size));
+    text.push_str(&format!("[Players \"{}\"]\n", board.num_players));
+    text.push_str(&format!("[Notation \"{}\"]\n", match notation { Relative => "relative", Absolute => "absolute" }));
+    if start.hash != Board::with_players(board.size, board.num_players).hash {
+        text.push_str(&format!("[Position \"{}\"]\n", start.to_position_string()));
+    }
+    text.push('\n');
+
+    //moves are numbered in rounds, a game not starting with player 1 to move starts with '1...'
+    let mut b = start.clone();
+    let mut line = String::new();
+    for (i, m) in board.move_sequence.iter().enumerate() {
+        let mut token = _move_number(&start, i);
+        token.push_str(&string_from_move(m, &b, notation));
+        b.mv(m);
+
+        if line.len() + token.len() > 79 {
+            text.push_str(line.trim_end());
+            text.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+        line.push(' ');
+    }
+    line.push_str(&result);
+    text.push_str(&line);
+    text.push('\n');
+    text
+}
+
+fn _move_number(start: &Board, i: usize) -> String {
+    //the number written before move i of a game from start. Only the moves of player 1 and the first move are numbered
+    let turn = start.to_move.index() + i;
+    let number = turn / start.num_players + 1;
+    match (turn % start.num_players, i) {
+        (0, _) => format!("{}. ", number),
+        (_, 0) => format!("{}... ", number),
+        _ => String::new(),
+    }
+}
+
+pub fn read_record(content: &str) -> Result<Record, LoadSaveError> {
+    //rejects records with an illegal move, naming the move number and why it is illegal,
+    //and records with a result the moves do not lead to
+    let mut notation = Absolute;
+    let (mut size, mut players, mut position) = (9, 2, None);
+    let mut names = vec!["?".to_string(); 4];
+    let (mut date, mut result) = ("????.??.??".to_string(), None);
+    let mut movetext = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if !line.starts_with('[') {
+            movetext.push_str(line);
+            movetext.push(' ');
+            continue;
+        }
+        let Some((tag, value)) = line.trim_start_matches('[').trim_end_matches(']').split_once(' ') else {
+            return Err(CorruptedGame);
+        };
+        let value = value.trim().trim_matches('"');
+        match tag {
+            "Size" => size = match value.parse::<usize>() {
+                Ok(size) if Board::is_valid_size(size) => size,
+                _ => return Err(UnsupportedBoardSize),
+            },
+            "Players" => players = match value {
+                "2" => 2,
+                "4" => 4,
+                _ => return Err(CorruptedGame),
+            },
+            "Notation" => notation = match value {
+                "relative" => Relative,
+                "absolute" => Absolute,
+                _ => return Err(CorruptedGame),
+            },
+            "Position" => position = Some(Board::from_position_string(value).map_err(|_| CorruptedGame)?),
+            "Player1" => names[0] = value.to_string(),
+            "Player2" => names[1] = value.to_string(),
+            "Player3" => names[2] = value.to_string(),
+            "Player4" => names[3] = value.to_string(),
+            "Date" => date = value.to_string(),
+            "Result" => result = Some(value.to_string()),
+            _ => (),
+        }
+    }
+    let mut board = position.unwrap_or(Board::with_players(size, players));
+    names.truncate(board.num_players);
+
+    //results are '*' or scores separated by dashes, moves never contain a dash.
+    //move numbers may be written apart from or glued to the move, like '1. e2' or '1.e2'
+    let moves: Vec<&str> = movetext.split_whitespace()
+        .take_while(|token| *token != "*" && !token.contains('-'))
+        .map(|token| token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.'))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let start = board.clone();
+    if let Err((i, err)) = board.try_extend(&moves, notation) {
+        let turn = start.to_move.index() + i;
+        let label = match turn % start.num_players {
+            0 => format!("{}. {}", turn / start.num_players + 1, moves[i]),
+            _ => format!("{}... {}", turn / start.num_players + 1, moves[i]),
+        };
+        return Err(match err {
+            Some(err) => IllegalRecordMove(label, err),
+            None => InvalidRecordMove(label),
+        });
+    }
+    if result.is_some_and(|r| r != _result(&board)) { return Err(WrongResult) };
+    Ok(Record { board, names, date })
+}
+
+fn _today() -> String {
+    //the current date as yyyy.mm.dd, converted from days since 1970-01-01
+    let secs = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe/1460 + doe/36524 - doe/146096) / 365;
+    let doy = doe - (365*yoe + yoe/4 - yoe/100);
+    let mp = (5*doy + 2) / 153;
+    let d = doy - (153*mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{}.{:02}.{:02}", y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{ MoveError, Notation };
+
+    fn _record(position: &str, moves: &str, names: &[&str]) -> Record {
+        let mut board = Board::from_position_string(position).unwrap();
+        board.extend_no_check(moves, Absolute);
+        Record { board, names: names.iter().map(|n| n.to_string()).collect(), date: "2024.05.01".to_string() }
+    }
+
+    fn _round_trip(record: &Record, notation: Notation) {
+        let read = read_record(&write_record(record, notation)).unwrap();
+        assert_eq!(read.board.hash, record.board.hash);
+        assert_eq!(read.board.move_sequence, record.board.move_sequence);
+        assert_eq!(read.board.start_position().hash, record.board.start_position().hash);
+        assert_eq!(read.names, record.names);
+        assert_eq!(read.date, record.date);
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = [
+            _record("9 / e1 e9 / 10 10 / 1 /", "e2 e8 e3 e7h", &["Alice", "Bob"]),
+            //a Position tag, player 2 to move first and a finished game
+            _record("7 / d5 d4 / 3 2 / 2 / c4h d3v", "d3 d6 d2 d7", &["?", "Bob"]),
+            //four players, moves numbered in rounds of four
+            _record("9 / e1 a5 e9 i5 / 5 5 5 5 / 1 /", "e2 b5 e8 h5 e3h d6v e7 g5", &["A", "B", "C", "D"]),
+        ];
+        assert!(write_record(&records[1], Absolute).contains("[Position \"7 / d5 d4 / 3 2 / 2 / c4h d3v\"]"));
+        assert!(write_record(&records[1], Absolute).contains("[Result \"1-0\"]"));
+        assert!(write_record(&records[2], Absolute).contains("[Player4 \"D\"]"));
+        assert!(write_record(&records[0], Absolute).ends_with("\n1. e2 e8 2. e3 e7h *\n"));
+        assert!(write_record(&records[1], Absolute).ends_with("\n1... d3 2. d6 d2 3. d7 1-0\n"));
+        assert!(write_record(&records[2], Absolute).ends_with("\n1. e2 b5 e8 h5 2. e3h d6v e7 g5 *\n"));
+        for record in &records {
+            _round_trip(record, Absolute);
+            _round_trip(record, Relative);
+        }
+    }
+
+    #[test]
+    fn rejected_records() {
+        let header = "[Size \"9\"]\n[Notation \"absolute\"]\n";
+        assert!(read_record(&format!("{}[Result \"*\"]\n1. e2 e8 2. e3 *", header)).is_ok());
+        //a result the moves do not lead to
+        assert_eq!(read_record(&format!("{}[Result \"1-0\"]\n1. e2 e8 1-0", header)).err(), Some(WrongResult));
+        //illegal and invalid moves are named with their number
+        assert_eq!(read_record(&format!("{}1. e2 e8 2. e4 *", header)).err(), Some(IllegalRecordMove("2. e4".to_string(), MoveError::OpponentNotThere)));
+        assert_eq!(read_record(&format!("{}1. e2 e8 2. e3 x9 *", header)).err(), Some(InvalidRecordMove("2... x9".to_string())));
+    }
+}
@@ -1,22 +1,91 @@
-// contains functionality for saving / loading a game to / from a file!()
+// contains functionality for saving / loading a game to / from a file
+//
+// A file holds any number of games. Every game starts with its tag in square brackets,
+// followed by the position the game started from and the moves played since, for example
+//
+//     [opening 1]
+//     position: 9 / e1 e9 / 10 10 / 1 / d3h e6v
+//     moves: N S N S Hg3
+//
+// The position is written like Board::to_position_string, the moves in relative notation.
 
-use std::fs::File;
-use std::io::prelude::*;
+use std::collections::HashMap;
+use std::fs;
 
-use crate::{
-    Board, Board::*,
-    Notation,
+use crate::Board;
+use crate::enums::{
+    MoveError,
+    Notation::*,
     LoadSaveError, LoadSaveError::* };
+use crate::helpers::string_from_move;
 
+pub const DEFAULT_TAG: &str = "default";
 
+pub fn save(board: &Board, path: &str, tag: &str) -> Result<(), LoadSaveError> {
+    //adds the game to the file, replacing a game with the same tag. The file is created if it does not exist
+    let mut games = match fs::read_to_string(path) {
+        Ok(content) => _split_games(&content)?,
+        Err(_) => vec![],
+    };
 
-pub fn load(path: String, game_tag: String) -> Result<Board, LoadSaveError> {
-    let file;
-    match File::open() {
-        Ok(f) => file = f,
-        Err() => return FileReadError,
+    let entry = _game_to_string(board);
+    match games.iter_mut().find(|(t, _)| t == tag) {
+        Some(game) => game.1 = entry,
+        None => games.push((tag.to_string(), entry)),
     }
 
+    let content: Vec<String> = games.iter().map(|(t, g)| format!("[{}]\n{}", t, g)).collect();
+    fs::write(path, content.join("\n")).map_err(|_| FileNotWritable)
+}
+
+pub fn load(path: &str, tag: Option<&str>, move_errors: &HashMap<MoveError, String>) -> Result<Board, LoadSaveError> {
+    //loads the game with the given tag, or the first game of the file if no tag is given
+    let content = fs::read_to_string(path).map_err(|_| FileNotFound)?;
+    let games = _split_games(&content)?;
+    let game = match tag {
+        Some(tag) => games.iter().find(|(t, _)| t == tag),
+        None => games.first(),
+    };
+    match game {
+        Some((_, g)) => _game_from_string(g, move_errors),
+        None => Err(TagNotFound),
+    }
+}
+
+fn _split_games(content: &str) -> Result<Vec<(String, String)>, LoadSaveError> {
+    //splits a file into (tag, game) pairs
+    let mut games: Vec<(String, String)> = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() { continue };
+        if line.starts_with('[') && line.ends_with(']') {
+            games.push((line[1..line.len()-1].to_string(), String::new()));
+        } else {
+            match games.last_mut() {
+                Some((_, game)) => { game.push_str(line); game.push('\n'); },
+                None => return Err(CorruptedGame),
+            }
+        }
+    }
+    Ok(games)
+}
+
+fn _game_to_string(board: &Board) -> String {
+    let moves: Vec<String> = board.move_sequence.iter().map(|m| string_from_move(m, board, Relative)).collect();
+    format!("position: {}\nmoves: {}\n", board.start_position().to_position_string(), moves.join(" "))
+}
+
+fn _game_from_string(game: &str, move_errors: &HashMap<MoveError, String>) -> Result<Board, LoadSaveError> {
+    let mut fields = HashMap::new();
+    for line in game.lines() {
+        match line.split_once(':') {
+            Some((key, value)) => { fields.insert(key.trim(), value.trim()); },
+            None => return Err(CorruptedGame),
+        }
+    }
+    let field = |key: &str| fields.get(key).copied().ok_or(CorruptedGame);
 
-    let games = String::new();
+    let mut b = Board::from_position_string(field("position")?).map_err(|_| CorruptedGame)?;
+    b.extend(field("moves")?, move_errors, Relative).map_err(IllegalMoveList)?;
+    Ok(b)
 }
@@ -0,0 +1,102 @@
+//contains the transposition table shared by all search threads
+
+use crate::Board;
+use crate::enums::{ Move, Move::*, Direction, Turn::* };
+
+use std::sync::atomic::{ AtomicU64, Ordering::Relaxed };
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub depth: usize,
+    pub score: isize,
+    pub bound: Bound,
+    pub best: Option<Move>,
+}
+
+pub struct TranspositionTable {
+    //every slot is [key ^ data, data], so a slot torn by two threads writing at once fails the key check
+    slots: Vec<[AtomicU64; 2]>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    pub fn new(log2_slots: usize) -> TranspositionTable {
+        TranspositionTable {
+            slots: (0..1 << log2_slots).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(),
+            mask: (1 << log2_slots) - 1,
+        }
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        let [k, d] = &self.slots[hash as usize & self.mask];
+        let data = d.load(Relaxed);
+        if data == 0 || k.load(Relaxed) ^ data != hash { return None };
+        Some(_unpack(data))
+    }
+
+    pub fn store(&self, hash: u64, entry: &Entry) {
+        //always replaces, unless the slot holds a deeper search of the same position
+        let [k, d] = &self.slots[hash as usize & self.mask];
+        if let Some(old) = self.probe(hash) {
+            if old.depth > entry.depth { return };
+        }
+        let data = _pack(entry);
+        k.store(hash ^ data, Relaxed);
+        d.store(data, Relaxed);
+    }
+}
+
+//data layout: bits 0..16 move, 16..24 depth, 24..26 bound, 32..64 score
+fn _pack(entry: &Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    encode_move(&entry.best)
+        | (entry.depth.min(255) as u64) << 16
+        | bound << 24
+        | (entry.score as i32 as u32 as u64) << 32
+}
+
+fn _unpack(data: u64) -> Entry {
+    Entry {
+        depth: (data >> 16 & 0xff) as usize,
+        score: (data >> 32) as u32 as i32 as isize,
+        bound: match data >> 24 & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+        best: decode_move(data & 0xffff),
+    }
+}
+
+pub fn encode_move(mv: &Option<Move>) -> u64 {
+    //pawn moves take the codes 1..=16, walls start at 256. Every code fits in 16 bits
+    match mv {
+        None => 0,
+        Some(Step(d)) => 1 + *d as u64,
+        Some(Jump(d)) => 5 + *d as u64,
+        Some(DiagonalJump(d, t)) => 9 + 2 * *d as u64 + *t as u64,
+        Some(Wall { orientation, x, y }) => (256 + 256 * *orientation as usize + 16*y + x) as u64,
+    }
+}
+
+pub fn decode_move(code: u64) -> Option<Move> {
+    let code = code as usize;
+    match code {
+        0 => None,
+        1..=4 => Some(Step(Direction::ALL[code-1])),
+        5..=8 => Some(Jump(Direction::ALL[code-5])),
+        9..=16 => Some(DiagonalJump(Direction::ALL[(code-9) / 2], [Left, Right][(code-9) % 2])),
+        _ => Some(Board::wall((code-256) / 256, (code-256) % 16, (code-256) / 16 % 16)),
+    }
+}
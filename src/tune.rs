@@ -0,0 +1,125 @@
+//contains the tuning of the evaluation weights from self-play games (texel tuning).
+//the engine plays itself, every position of a game is labelled with the result of the game,
+//then the weights are fitted so the evaluation of a position predicts the result of its game
+
+use crate::Board;
+use crate::brute::iterative_deepening;
+use crate::eval::{ Weights, evaluate };
+use crate::tt::TranspositionTable;
+use crate::bots::mostly_pawn_move;
+
+//games not decided after this many moves count as draws
+const MAX_GAME_LENGTH: usize = 200;
+//random moves at the start of every game, so the games differ
+const RANDOM_MOVES: usize = 4;
+//the largest tuned weight. Only the ratios of the weights matter to the search, this sets the resolution
+const WEIGHT_SCALE: f64 = 10.0;
+
+//the evaluation terms of a position with all weights 1, and the result of its game for player 1: 1, 0.5 or 0
+type Sample = ([f64; 3], f64);
+
+pub fn tune(games: usize, depth: usize, weights: &Weights) -> Weights {
+	//plays the games with the given weights, prints the prediction error before and after and returns the tuned weights
+	let mut samples = vec![];
+	let mut state = 0x9e3779b97f4a7c15;
+	for game in 0..games {
+		let (positions, result) = _self_play(depth, weights, &mut state);
+		let outcome = if result == 1.0 { "player 1 wins" } else if result == 0.0 { "player 2 wins" } else { "draw" };
+		println!("    Game {:>4}:    {}", game+1, outcome);
+		samples.extend(positions.iter().map(|f| (*f, result)));
+	}
+	println!("    {} positions from {} games", samples.len(), games);
+	if samples.is_empty() { return *weights };
+
+	let current = _as_vector(weights);
+	let k = _best_scale(&samples, current);
+	println!("    Error with the current weights: {:.5}", _error(&samples, current.map(|w| w*k)));
+
+	let fitted = _fit(&samples, current.map(|w| w*k));
+	let largest = fitted.iter().fold(0.0, |m: f64, w| m.max(w.abs()));
+	if largest == 0.0 { return *weights };
+	let rounded = fitted.map(|w| (w * WEIGHT_SCALE / largest).round());
+	let tuned = Weights { walls: rounded[0] as isize, paths: rounded[1] as isize, tempo: rounded[2] as isize };
+	let k = _best_scale(&samples, rounded);
+	println!("    Error with the tuned weights:   {:.5}", _error(&samples, rounded.map(|w| w*k)));
+	tuned
+}
+
+fn _self_play(depth: usize, weights: &Weights, state: &mut u64) -> (Vec<[f64; 3]>, f64) {
+	//one game on the usual board. Returns the terms of every position before the game was decided and the result
+	let mut board = Board::new();
+	let tt = TranspositionTable::new(18);
+	let mut positions = vec![];
+	while !board.is_over() && board.move_sequence.len() < MAX_GAME_LENGTH {
+		if board.move_sequence.len() >= RANDOM_MOVES {
+			positions.push(_terms(&board));
+		}
+		let m = if board.move_sequence.len() < RANDOM_MOVES {
+			mostly_pawn_move(&board.all_legal_moves(), state)
+		} else {
+			iterative_deepening(&board, depth, None, &tt, weights, &mut |_| ()).line.first().copied()
+		};
+		match m {
+			Some(m) => board.mv(&m),
+			None => break,
+		}
+	}
+	//a game reaching the goal with its last move allowed is decided all the same
+	match board.goal_reached() {
+		Some(winner) => (positions, if winner == 0 { 1.0 } else { 0.0 }),
+		None => (positions, 0.5),
+	}
+}
+
+fn _terms(board: &Board) -> [f64; 3] {
+	let e = evaluate(board, &Weights { walls: 1, paths: 1, tempo: 1 });
+	[e.walls as f64, e.paths as f64, e.tempo as f64]
+}
+
+fn _as_vector(weights: &Weights) -> [f64; 3] {
+	[weights.walls as f64, weights.paths as f64, weights.tempo as f64]
+}
+
+fn _predict(terms: &[f64; 3], w: [f64; 3]) -> f64 {
+	//the chance of player 1 winning the evaluation stands for
+	let score: f64 = terms.iter().zip(w).map(|(t, w)| t*w).sum();
+	1.0 / (1.0 + (-score).exp())
+}
+
+fn _error(samples: &[Sample], w: [f64; 3]) -> f64 {
+	samples.iter().map(|(terms, result)| (_predict(terms, w) - result).powi(2)).sum::<f64>() / samples.len() as f64
+}
+
+fn _best_scale(samples: &[Sample], w: [f64; 3]) -> f64 {
+	//the factor turning scores into winning chances best. Scores of the search are integers, chances are not.
+	//searched as a power of e, from e^-8 to e^8
+	let error = |x: f64| _error(samples, w.map(|w| w * x.exp()));
+	let mut x = 0.0;
+	let mut step = 4.0;
+	while step > 1e-4 {
+		for candidate in [x - step, x + step] {
+			if error(candidate) < error(x) { x = candidate };
+		}
+		step /= 2.0;
+	}
+	x.exp()
+}
+
+fn _fit(samples: &[Sample], mut w: [f64; 3]) -> [f64; 3] {
+	//gradient descent on the mean squared error of the predicted results
+	if samples.is_empty() { return w };
+	let rate = 0.05;
+	for _ in 0..2000 {
+		let mut gradient = [0.0; 3];
+		for (terms, result) in samples {
+			let p = _predict(terms, w);
+			for (g, t) in gradient.iter_mut().zip(terms) {
+				*g += 2.0 * (p - result) * p * (1.0 - p) * t;
+			}
+		}
+		for (w, g) in w.iter_mut().zip(gradient) {
+			*w -= rate * g / samples.len() as f64;
+		}
+	}
+	w
+}
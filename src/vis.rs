@@ -1,13 +1,15 @@
 //contains functionality for printing the board. Will be expanded to make coloring possible
 
 use crate::InteractiveInstance;
-use crate::enums::{ Color, Color::*, Notation, Notation::*, Move, Move::*, Flag::* };
+use crate::Board;
+use crate::enums::{ Color, Color::*, Notation, Notation::*, Flag::*, LegalFilter, Move::*, Orientation::* };
 use crate::helpers::string_from_move;
 
 use colored::ColoredString;
 use colored::Colorize;
 
-fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8]; 2], squares: &[[Color; 9]; 9], to_move: &usize) -> ColoredString {
+fn _c (x: usize, y: usize, size: usize, players: &[[usize; 2]], walls: &[Vec<Vec<Color>>; 2], squares: &[Vec<Color>], to_move: &usize) -> ColoredString {
+	//x and y are character positions, every square is 8 characters wide and 4 high including its borders
 	match (x%8 == 7, y%4 == 3) {
 		(true, true) => {
 			match &walls[0][x/8][y/4] {
@@ -26,7 +28,7 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 			}
 		},
 		(true, false) =>
-			match (y/4 == 0, y/4 == 8) {
+			match (y/4 == 0, y/4 == size-1) {
 				(true, true) => "oop".into(),
 				(true, false) => {
 					match &walls[1][x/8][0] {
@@ -38,7 +40,7 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 					}
 				},
 				(false, true) => {
-					match &walls[1][x/8][7] {
+					match &walls[1][x/8][size-2] {
 						Empty => return ".".into(),
 						White => return "|".black().on_white(),
 						//Red => return "|".black().on_red(),
@@ -64,7 +66,7 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 				}
 			},
 		(false, true) => 
-			match (x/8 == 0, x/8 == 8) {
+			match (x/8 == 0, x/8 == size-1) {
 				(true, true) => "oops".into(),
 				(true, false) => 
 					match &walls[0][0][y/4] {
@@ -75,7 +77,7 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 						//Blue => return "-".black().on_blue(),
 					},
 				(false, true) =>
-					match &walls[0][7][y/4] {
+					match &walls[0][size-2][y/4] {
 						Empty => return ".".into(),
 					White => return "-".black().on_white(),
 					//Red => return "-".black().on_red(),
@@ -110,11 +112,11 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 						//Blue => return " ".on_blue(),
 					},
 					Some(i) => match &squares[x/8][y/4] {
-						Empty => if i == *to_move { return (i+1).to_string().bold().blue() } else { return (i+1).to_string().bold() },
-						White => if i == *to_move { return (i+1).to_string().on_white().bold().blue() } else { return (i+1).to_string().bold() },
-						//Red => if i == *to_move { return (i+1).to_string().on_red().bold().blue() } else { return (i+1).to_string().bold() },
-						//Green => if i == *to_move { return (i+1).to_string().on_green().bold().blue() } else { return (i+1).to_string().bold() },
-						//Blue => if i == *to_move { return (i+1).to_string().on_blue().bold().blue() } else { return (i+1).to_string().bold() },
+						Empty => return _pawn(i, *to_move),
+						White => return _pawn(i, *to_move).on_white(),
+						//Red => return _pawn(i, *to_move).on_red(),
+						//Green => return _pawn(i, *to_move).on_green(),
+						//Blue => return _pawn(i, *to_move).on_blue(),
 					},
 				};
 			} else {
@@ -129,59 +131,67 @@ fn _c (x: usize, y: usize, players: &Vec<[usize; 2]>, walls: &[[[Color; 8]; 8];
 		}
 	}
 }
+fn _pawn(i: usize, to_move: usize) -> ColoredString {
+	//every player has their own colour, the pawn of the player to move is underlined
+	let pawn = match i {
+		0 => (i+1).to_string().bold().red(),
+		1 => (i+1).to_string().bold().blue(),
+		2 => (i+1).to_string().bold().green(),
+		_ => (i+1).to_string().bold().yellow(),
+	};
+	if i == to_move { pawn.underline() } else { pawn }
+}
+
+fn _header(size: usize, notation: Notation) -> String {
+	//letters name the columns of squares in absolute notation, the columns of walls in relative notation
+	let letters = "abcdefghijk";
+	let mut line = String::from("       -");
+	for x in 0..8*size-1 {
+		match (notation, x%8) {
+			(Absolute, 3) | (Relative, 7) => line.push(letters.chars().nth(x/8).unwrap()),
+			_ => line.push('-'),
+		}
+	}
+	line.push('-');
+	line
+}
+
 fn show(players: Vec<[usize; 2]>,
 		to_move: usize,
-		walls: [[[Color; 8]; 8]; 2],
-		squares: [[Color; 9]; 9],
+		walls: [Vec<Vec<Color>>; 2],
+		squares: Vec<Vec<Color>>,
 		invert: bool,
-		walls_left: [usize; 2],
+		walls_left: Vec<usize>,
 		notation: Notation,
-		moves: Vec<Move>) {
-	match notation {
-		Relative => {
-			print!("       --------a-------b-------c-------d-------e-------f-------g-------h--------\n");
-			for yy in (0..35).rev() {
-				let y = if invert { 34-yy } else { yy };
-				if y%4 == 3 {
-					print!("       {}", y/4+1);
-				} else {print!("       |");};
-				for x in 0..71 {
-					print!("{}", _c(x, y, &players, &walls, &squares, &to_move));
-				}
-				if y%4 == 3 {
-					print!("{}\n", y/4+1);
-				} else { print!("|\n"); };
-			}
-			print!("       --------a-------b-------c-------d-------e-------f-------g-------h--------\n\n");
-		},
-		Absolute => {
-			print!("       ----a-------b-------c-------d-------e-------f-------g-------h-------i----\n");
-			for yy in (0..35).rev() {
-				let y = if invert { 34-yy } else { yy };
-				if y%4 == 1 {
-					print!("       {}", y/4+1);
-				} else {print!("       |");};
-				for x in 0..71 {
-					print!("{}", _c(x, y, &players, &walls, &squares, &to_move));
-				}
-				if y%4 == 1 {
-					print!("{}\n", y/4+1);
-				} else { print!("|\n"); };
-			}
-			print!("       ----a-------b-------c-------d-------e-------f-------g-------h-------i----\n\n");
+		moves: Vec<String>) {
+	let size = squares.len();
+	let height = 4*size-1;
+	//rows are labeled at the wall lines in relative notation, at the squares in absolute notation
+	let label_row = match notation {
+		Relative => 3,
+		Absolute => 1,
+	};
+	println!("{}", _header(size, notation));
+	for yy in (0..height).rev() {
+		let y = if invert { height-1-yy } else { yy };
+		if y%4 == label_row {
+			print!("{:>8}", y/4+1);
+		} else {print!("       |");};
+		for x in 0..8*size-1 {
+			print!("{}", _c(x, y, size, &players, &walls, &squares, &to_move));
 		}
+		if y%4 == label_row {
+			println!("{}", y/4+1);
+		} else { println!("|"); };
 	}
-	println!("         Walls:      Player 1: {}               Player 2: {}", walls_left[0], walls_left[1]);
+	print!("{}\n\n", _header(size, notation));
+	let walls_left: Vec<String> = walls_left.iter().enumerate().map(|(p, w)| format!("Player {}: {}", p+1, w)).collect();
+	println!("         Walls:      {}", walls_left.join(if walls_left.len() == 2 { "               " } else { "       " }));
 	print!("         Moves:      ");
 	let mut char_printed=0;
 	for mv in moves {
-		print!("{} ", string_from_move(&mv, players[to_move], notation.clone()));
-		match (mv, notation) {
-			(Step(_), Absolute) => char_printed += 3,
-			(Wall(_), Absolute) => char_printed += 4,
-			(Step(_), Relative) => char_printed += 2,
-			(Wall(_), Relative) => char_printed += 3,
-		}
+		print!("{} ", mv);
+		char_printed += mv.len()+1;
 		if char_printed > 50 {
 			println!("");
 			print!("                     ");
@@ -191,12 +201,13 @@ fn show(players: Vec<[usize; 2]>,
 	println!("\n\n");
 }
 
-fn _default_wall_colors(walls: &u128) -> [[[Color; 8]; 8]; 2] {
-	let mut res = [[[Empty; 8]; 8]; 2];
-	for i in 0..=1 {
-		for y in 0..=7 {
-			for x in 0..=7 {
-				if walls & (1 << (64*i + 8*x + y)) != 0 {res[i][y][x] = White};
+fn _default_wall_colors(b: &Board) -> [Vec<Vec<Color>>; 2] {
+	let n = b.size-1;
+	let mut res = [vec![vec![Empty; n]; n], vec![vec![Empty; n]; n]];
+	for (i, walls) in res.iter_mut().enumerate() {
+		for (x, column) in walls.iter_mut().enumerate() {
+			for (y, color) in column.iter_mut().enumerate() {
+				if b.is_wall(i, x, y) { *color = White };
 			}
 		}
 	}
@@ -206,13 +217,58 @@ fn _default_wall_colors(walls: &u128) -> [[[Color; 8]; 8]; 2] {
 pub fn print_board(ii: &InteractiveInstance) {
 	let b = &ii.board;
 	let invert = ii.flags.get(&Invert).unwrap();
-	show(b.players.to_vec(),
+	//every move is written from the position it was played in
+	let mut position = b.start_position();
+	let mut moves = vec![];
+	for m in &b.move_sequence {
+		moves.push(string_from_move(m, &position, ii.notation));
+		position.mv(m);
+	}
+	show(b.pawns().to_vec(),
 		b.to_move_indices().0,
-		_default_wall_colors(&b.walls),
-		[[Empty; 9]; 9], *invert, [b.walls_left[0],
-		b.walls_left[1]], ii.notation.clone(),
-		ii.board.move_sequence.clone());
+		_default_wall_colors(b),
+		vec![vec![Empty; b.size]; b.size], *invert,
+		b.walls_left[..b.num_players].to_vec(), ii.notation,
+		moves);
 
 	//println!("score: {}", 2*(ii.board.dist_to_goal(1).unwrap() as isize - ii.board.dist_to_goal(0).unwrap() as isize));
 	//println!("dists: {}, {}", ii.board.dist_to_goal(1).unwrap(), ii.board.dist_to_goal(0).unwrap());
 }
+
+pub fn print_legal_moves(b: &Board, filter: LegalFilter, notation: Notation) {
+	//pawn moves sorted by the square they land on, then the walls by orientation, column and row
+	let pos = b.players[b.to_move_indices().0];
+	let near = |[x, y]: [usize; 2], [a, c]: [usize; 2]| x.abs_diff(a) <= 1 && y.abs_diff(c) <= 1;
+	let mut moves = b.clone().all_legal_moves();
+	moves.retain(|m| match (filter, m) {
+		(LegalFilter::All, _) => true,
+		(LegalFilter::Steps, Wall { .. }) | (LegalFilter::Walls, Step(_) | Jump(_) | DiagonalJump(..)) => false,
+		(LegalFilter::Steps, _) | (LegalFilter::Walls, _) => true,
+		//a wall touches the square if the square is one of the four around its centre
+		(LegalFilter::Near([a, c]), Wall { x, y, .. }) => (*x == a || *x+1 == a) && (*y == c || *y+1 == c),
+		(LegalFilter::Near(square), _) => near(Board::landing(pos, m), square),
+	});
+	let (mut pawn_moves, mut walls): (Vec<_>, Vec<_>) = moves.into_iter().partition(|m| !matches!(m, Wall { .. }));
+	pawn_moves.sort_by_key(|m| { let [x, y] = Board::landing(pos, m); (x, y) });
+	walls.sort_by_key(|m| match m { Wall { orientation, x, y } => (*orientation as usize, *x, *y), _ => (0, 0, 0) });
+
+	if pawn_moves.is_empty() && walls.is_empty() {
+		println!("    No legal moves{}.", if b.is_over() { ", the game is over" } else { "" });
+		return;
+	}
+	let strings = |moves: Vec<_>| moves.iter().map(|m| string_from_move(m, b, notation)).collect::<Vec<_>>();
+	let horizontal: Vec<_> = walls.iter().copied().filter(|m| matches!(m, Wall { orientation: Horizontal, .. })).collect();
+	let vertical: Vec<_> = walls.iter().copied().filter(|m| matches!(m, Wall { orientation: Vertical, .. })).collect();
+	_print_group("Pawn moves", strings(pawn_moves));
+	_print_group("Horizontal walls", strings(horizontal));
+	_print_group("Vertical walls", strings(vertical));
+}
+
+fn _print_group(name: &str, moves: Vec<String>) {
+	//one group of moves behind its name and count, wrapped to lines of ten moves
+	if moves.is_empty() { return };
+	let label = format!("{} ({}):", name, moves.len());
+	for (i, line) in moves.chunks(10).enumerate() {
+		println!("    {:<24}{}", if i == 0 { label.as_str() } else { "" }, line.join(" "));
+	}
+}
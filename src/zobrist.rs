@@ -0,0 +1,29 @@
+//contains the zobrist keys used to hash boards. The keys are generated at compile time, so hashes are the same every run
+
+const fn _splitmix(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn _keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        keys[i] = _splitmix(seed.wrapping_mul(0x100000001b3).wrapping_add(i as u64));
+        i += 1;
+    }
+    keys
+}
+
+//one key per size, so boards of different sizes do not share hashes
+pub const SIZE_KEYS: [u64; 12] = _keys(7);
+//one key per bit of both Board::walls bitmaps, enough for 11x11 boards
+pub const WALL_KEYS: [[u64; 100]; 2] = [_keys(1), _keys(8)];
+//one key per square and player, square index is size*y + x
+pub const SQUARE_KEYS: [[u64; 121]; 4] = [_keys(2), _keys(3), _keys(9), _keys(10)];
+//one key per number of walls left and player. Also limits how many walls a player can have
+pub const WALLS_LEFT_KEYS: [[u64; 32]; 4] = [_keys(4), _keys(5), _keys(11), _keys(12)];
+//xored in for the player to move, player 1 to move adds nothing
+pub const TO_MOVE_KEYS: [u64; 4] = [0, _splitmix(6), _splitmix(13), _splitmix(14)];